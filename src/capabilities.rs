@@ -0,0 +1,47 @@
+use anyhow::{bail, Result};
+use caps::{CapSet, CapsHashSet};
+
+use crate::spec::{LinuxCapabilities, LinuxCapabilityType};
+
+fn to_set(caps: &[LinuxCapabilityType]) -> CapsHashSet {
+    caps.iter().map(|c| c.cap).collect()
+}
+
+// Raise every permitted capability in the effective set, which is cleared when the process
+// switches to a non-root user.
+pub fn reset_effective() -> Result<()> {
+    let permitted = caps::read(None, CapSet::Permitted)?;
+    caps::set(None, CapSet::Effective, &permitted)?;
+    Ok(())
+}
+
+pub fn drop_privileges(cs: &LinuxCapabilities) -> Result<()> {
+    log::debug!("dropping bounding capabilities to {:?}", cs.bounding);
+    let bounding = to_set(&cs.bounding);
+    // capabilities unknown to the running kernel are not in the bounding set anyway
+    for cap in caps::runtime::thread_all_supported() {
+        if !bounding.contains(&cap) {
+            caps::drop(None, CapSet::Bounding, cap)?;
+        }
+    }
+
+    // The effective set has to stay a subset of the permitted set, so it goes first, while the
+    // permitted set is still the one of root.
+    set(CapSet::Effective, &cs.effective)?;
+    set(CapSet::Permitted, &cs.permitted)?;
+    set(CapSet::Inheritable, &cs.inheritable)?;
+    // ambient capabilities are only kept by execve when they are also permitted and inheritable
+    if caps::runtime::ambient_set_supported().is_ok() {
+        set(CapSet::Ambient, &cs.ambient)?;
+    } else if !cs.ambient.is_empty() {
+        bail!("ambient capabilities are not supported by the kernel");
+    }
+    Ok(())
+}
+
+fn set(cset: CapSet, caps: &[LinuxCapabilityType]) -> Result<()> {
+    if let Err(e) = caps::set(None, cset, &to_set(caps)) {
+        bail!("failed to set {:?} capabilities {:?}: {}", cset, caps, e);
+    }
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use nix::sys::statfs;
+use nix::unistd::Pid;
+
+use crate::cgroups::oom::OomNotifier;
+use crate::cgroups::stats::Stats;
+use crate::cgroups::{v1, v2};
+use crate::rootfs;
+use crate::rootless;
+use crate::spec::{Linux, LinuxDeviceCgroup, LinuxDeviceType, LinuxResources};
+
+pub const DEFAULT_CGROUP_ROOT: &str = "/sys/fs/cgroup";
+pub const CGROUP_PROCS: &str = "cgroup.procs";
+
+// The parent of the cgroups of containers which don't specify linux.cgroupsPath.
+const DEFAULT_CGROUP_PARENT: &str = "/rust-runtime";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FreezerState {
+    Frozen,
+    Thawed,
+}
+
+pub trait CgroupManager {
+    // Create the cgroup, apply the resource limits and move the process into it.
+    fn apply(&self, linux_resources: &LinuxResources, pid: Pid) -> Result<()>;
+    // Change the limits of the existing cgroup. The device rules stay the ones of the creation.
+    fn update(&self, linux_resources: &LinuxResources) -> Result<()>;
+    // Remove the cgroup, which must not hold any process anymore.
+    fn remove(&self) -> Result<()>;
+    // Get the pids of every process in the cgroup.
+    fn get_all_pids(&self) -> Result<Vec<Pid>>;
+    // Freeze or thaw every process in the cgroup, returning once they all are.
+    fn freeze(&self, state: FreezerState) -> Result<()>;
+    // The directory of the cgroup that freezes the processes.
+    fn freezer_path(&self) -> Result<PathBuf>;
+    // Read the resource usage of the cgroup.
+    fn stats(&self) -> Result<Stats>;
+    // Get notified of the OOM events of the cgroup.
+    fn oom_notifier(&self) -> Result<OomNotifier>;
+}
+
+// Both absolute and relative cgroupsPath are interpreted relative to the cgroup root.
+pub fn get_cgroup_path(cgroups_path: &Option<PathBuf>, container_id: &str) -> PathBuf {
+    match cgroups_path {
+        Some(cgroups_path) => cgroups_path.clone(),
+        None => Path::new(DEFAULT_CGROUP_PARENT).join(container_id),
+    }
+}
+
+// The resources applied to the cgroup of a container. The device nodes that the runtime creates
+// in the rootfs stay usable whatever the device rules of the spec are.
+pub fn container_resources(linux: &Linux) -> LinuxResources {
+    let mut resources = linux.resources.clone().unwrap_or_default();
+    let allow = |typ, major, minor| LinuxDeviceCgroup {
+        allow: true,
+        typ,
+        major,
+        minor,
+        access: "rwm".to_string(),
+    };
+    for dev in rootfs::default_devices().iter().chain(&linux.devices) {
+        resources.devices.push(allow(
+            dev.typ,
+            Some(dev.major as i64),
+            Some(dev.minor as i64),
+        ));
+    }
+    // /dev/ptmx and the pseudo terminals of devpts
+    resources
+        .devices
+        .push(allow(LinuxDeviceType::C, Some(5), Some(2)));
+    resources
+        .devices
+        .push(allow(LinuxDeviceType::C, Some(136), None));
+    resources
+}
+
+pub fn is_unified_hierarchy() -> Result<bool> {
+    let fs_type = statfs::statfs(DEFAULT_CGROUP_ROOT)?.filesystem_type();
+    Ok(fs_type == statfs::CGROUP2_SUPER_MAGIC)
+}
+
+pub fn create_cgroup_manager<P: Into<PathBuf>>(cgroup_path: P) -> Result<Box<dyn CgroupManager>> {
+    let rootless = rootless::is_rootless();
+    if is_unified_hierarchy()? {
+        log::debug!("cgroup manager v2 will be used");
+        Ok(Box::new(v2::Manager::new(
+            PathBuf::from(DEFAULT_CGROUP_ROOT),
+            cgroup_path.into(),
+            rootless,
+        )))
+    } else {
+        log::debug!("cgroup manager v1 will be used");
+        Ok(Box::new(v1::Manager::new(cgroup_path.into(), rootless)?))
+    }
+}
+
+// Join a cgroup path to the root of a hierarchy, even if the cgroup path is absolute.
+pub fn join_cgroup_path(root_path: &Path, cgroup_path: &Path) -> PathBuf {
+    root_path.join(cgroup_path.strip_prefix("/").unwrap_or(cgroup_path))
+}
+
+pub fn write_cgroup_file<P: AsRef<Path>>(path: P, data: &str) -> Result<()> {
+    let path = path.as_ref();
+    log::debug!("write {:?} to {:?}", data, path);
+    let result = fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(data.as_bytes()));
+    if let Err(e) = result {
+        bail!("failed to write {:?} to {:?}: {}", data, path, e);
+    }
+    Ok(())
+}
+
+pub fn read_cgroup_pids<P: AsRef<Path>>(cgroup_path: P) -> Result<Vec<Pid>> {
+    let procs = fs::read_to_string(cgroup_path.as_ref().join(CGROUP_PROCS))?;
+    let mut pids = Vec::new();
+    for pid in procs.lines() {
+        pids.push(Pid::from_raw(pid.trim().parse()?));
+    }
+    Ok(pids)
+}
+
+// A cgroup stays busy for a moment after its last process has exited, so retry the removal.
+pub fn remove_cgroup_dir<P: AsRef<Path>>(cgroup_path: P) -> Result<()> {
+    let cgroup_path = cgroup_path.as_ref();
+    let mut retries = 10;
+    loop {
+        match fs::remove_dir(cgroup_path) {
+            Ok(()) => return Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) if retries == 0 => bail!("failed to remove {:?}: {}", cgroup_path, e),
+            Err(_) => {
+                retries -= 1;
+                thread::sleep(Duration::from_millis(100));
+            }
+        }
+    }
+}
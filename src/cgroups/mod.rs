@@ -0,0 +1,9 @@
+mod common;
+pub mod oom;
+pub mod stats;
+pub mod v1;
+pub mod v2;
+pub use common::{
+    container_resources, create_cgroup_manager, get_cgroup_path, is_unified_hierarchy,
+    CgroupManager, FreezerState, DEFAULT_CGROUP_ROOT,
+};
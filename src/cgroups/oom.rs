@@ -0,0 +1,121 @@
+use std::fs::File;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::PathBuf;
+
+use anyhow::Result;
+use nix::errno::Errno;
+use nix::sys::inotify::Inotify;
+use nix::unistd;
+
+use crate::cgroups::stats;
+
+// A file descriptor that becomes readable when the memory of a cgroup runs out.
+pub enum OomNotifier {
+    // cgroup v1 signals an eventfd registered in cgroup.event_control, whose memory.oom_control
+    // must stay open
+    EventFd {
+        eventfd: RawFd,
+        oom_control: File,
+    },
+    // cgroup v2 modifies memory.events, whose oom_kill counter grows
+    MemoryEvents {
+        inotify: Inotify,
+        path: PathBuf,
+        oom_kills: u64,
+    },
+}
+
+impl OomNotifier {
+    pub fn fd(&self) -> RawFd {
+        match self {
+            OomNotifier::EventFd { eventfd, .. } => *eventfd,
+            OomNotifier::MemoryEvents { inotify, .. } => inotify.as_raw_fd(),
+        }
+    }
+
+    // Consume the pending notifications and return the number of OOM events they stand for.
+    pub fn read(&mut self) -> Result<u64> {
+        match self {
+            OomNotifier::EventFd { eventfd, .. } => {
+                let mut buf = [0; 8];
+                match unistd::read(*eventfd, &mut buf) {
+                    Ok(_) => Ok(u64::from_ne_bytes(buf)),
+                    Err(nix::Error::Sys(Errno::EAGAIN)) => Ok(0),
+                    Err(e) => Err(e.into()),
+                }
+            }
+            OomNotifier::MemoryEvents {
+                inotify,
+                path,
+                oom_kills,
+            } => {
+                loop {
+                    match inotify.read_events() {
+                        Ok(_) => continue,
+                        Err(nix::Error::Sys(Errno::EAGAIN)) => break,
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+                let current = stats::read_flat_keyed(path)?
+                    .get("oom_kill")
+                    .copied()
+                    .unwrap_or(0);
+                let count = current.saturating_sub(*oom_kills);
+                *oom_kills = current;
+                Ok(count)
+            }
+        }
+    }
+}
+
+impl Drop for OomNotifier {
+    fn drop(&mut self) {
+        let _ = unistd::close(self.fd());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    use nix::sys::eventfd::{eventfd, EfdFlags};
+    use nix::sys::inotify::{AddWatchFlags, InitFlags};
+
+    const MEMORY_EVENTS: &str = "low 0\nhigh 0\nmax 3\noom 2\noom_kill 1\n";
+
+    #[test]
+    fn memory_events() {
+        let path =
+            std::env::temp_dir().join(format!("rust-runtime-memory.events-{}", std::process::id()));
+        fs::write(&path, MEMORY_EVENTS).unwrap();
+        let inotify = Inotify::init(InitFlags::IN_CLOEXEC | InitFlags::IN_NONBLOCK).unwrap();
+        inotify.add_watch(&path, AddWatchFlags::IN_MODIFY).unwrap();
+        let mut notifier = OomNotifier::MemoryEvents {
+            inotify,
+            path: path.clone(),
+            oom_kills: 0,
+        };
+
+        assert_eq!(notifier.read().unwrap(), 1);
+        assert_eq!(notifier.read().unwrap(), 0);
+        fs::write(&path, MEMORY_EVENTS.replace("oom_kill 1", "oom_kill 4")).unwrap();
+        assert_eq!(notifier.read().unwrap(), 3);
+        assert_eq!(notifier.read().unwrap(), 0);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn eventfd_counter() {
+        let fd = eventfd(0, EfdFlags::EFD_CLOEXEC | EfdFlags::EFD_NONBLOCK).unwrap();
+        let mut notifier = OomNotifier::EventFd {
+            eventfd: fd,
+            oom_control: File::open("/dev/null").unwrap(),
+        };
+
+        assert_eq!(notifier.read().unwrap(), 0);
+        unistd::write(fd, &2u64.to_ne_bytes()).unwrap();
+        assert_eq!(notifier.read().unwrap(), 2);
+        assert_eq!(notifier.read().unwrap(), 0);
+    }
+}
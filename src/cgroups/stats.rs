@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+const HUGEPAGES_DIR: &str = "/sys/kernel/mm/hugepages";
+
+// The resource usage of a cgroup, as reported by the events command.
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Stats {
+    pub cpu: CpuStats,
+    pub memory: MemoryStats,
+    pub pids: PidsStats,
+    pub blkio: BlkioStats,
+    // keyed by page size, e.g. 2MB
+    pub hugetlb: HashMap<String, HugeTlbStats>,
+}
+
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CpuStats {
+    pub usage: CpuUsage,
+    pub throttling: CpuThrottling,
+}
+
+// All times are in nanoseconds.
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CpuUsage {
+    pub total: u64,
+    // only reported by cgroup v1
+    pub per_cpu: Vec<u64>,
+    pub kernel: u64,
+    pub user: u64,
+}
+
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CpuThrottling {
+    pub periods: u64,
+    pub throttled_periods: u64,
+    pub throttled_time: u64,
+}
+
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryStats {
+    pub usage: MemoryData,
+    pub swap: MemoryData,
+    // the raw counters of memory.stat
+    pub stats: HashMap<String, u64>,
+}
+
+// A limit of u64::MAX means unlimited.
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryData {
+    pub usage: u64,
+    pub max_usage: u64,
+    pub limit: u64,
+    pub failcnt: u64,
+}
+
+// A limit of 0 means unlimited.
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PidsStats {
+    pub current: u64,
+    pub limit: u64,
+}
+
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BlkioStats {
+    pub io_service_bytes: Vec<BlkioEntry>,
+    pub io_serviced: Vec<BlkioEntry>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BlkioEntry {
+    pub major: u64,
+    pub minor: u64,
+    pub op: String,
+    pub value: u64,
+}
+
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct HugeTlbStats {
+    pub usage: u64,
+    pub max_usage: u64,
+    pub failcnt: u64,
+}
+
+// Read a file holding a single value, where max stands for no limit.
+pub fn read_value(path: &Path) -> Result<u64> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => bail!("failed to read {:?}: {}", path, e),
+    };
+    match content.trim() {
+        "max" => Ok(u64::MAX),
+        value => Ok(value.parse()?),
+    }
+}
+
+// Read a file of "key value" lines such as memory.stat or cpu.stat.
+pub fn read_flat_keyed(path: &Path) -> Result<HashMap<String, u64>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => bail!("failed to read {:?}: {}", path, e),
+    };
+    let mut values = HashMap::new();
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        if let (Some(key), Some(value)) = (fields.next(), fields.next()) {
+            values.insert(key.to_string(), value.parse()?);
+        }
+    }
+    Ok(values)
+}
+
+// Parse a device number such as 8:0.
+pub fn parse_device(device: &str) -> Result<(u64, u64)> {
+    match device.split_once(':') {
+        Some((major, minor)) => Ok((major.parse()?, minor.parse()?)),
+        None => bail!("invalid device number {}", device),
+    }
+}
+
+// The huge page sizes supported by the kernel, named as in the files of the hugetlb controller.
+pub fn hugepage_sizes() -> Result<Vec<String>> {
+    let mut sizes = Vec::new();
+    for entry in fs::read_dir(HUGEPAGES_DIR)? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        let kb: u64 = match name
+            .strip_prefix("hugepages-")
+            .and_then(|size| size.strip_suffix("kB"))
+        {
+            Some(kb) => kb.parse()?,
+            None => continue,
+        };
+        sizes.push(if kb >= 1 << 20 {
+            format!("{}GB", kb >> 20)
+        } else if kb >= 1 << 10 {
+            format!("{}MB", kb >> 10)
+        } else {
+            format!("{}KB", kb)
+        });
+    }
+    Ok(sizes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn write_file(name: &str, content: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("rust-runtime-{}-{}", name, std::process::id()));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn flat_keyed_file() {
+        let path = write_file(
+            "memory.stat",
+            "anon 4096\nfile 8192\n\nthrottled_usec 18446744073709551615\n",
+        );
+        let values = read_flat_keyed(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values["anon"], 4096);
+        assert_eq!(values["file"], 8192);
+        assert_eq!(values["throttled_usec"], u64::MAX);
+    }
+
+    #[test]
+    fn flat_keyed_file_with_an_invalid_value() {
+        let path = write_file("cpu.stat", "usage_usec 12\nnr_periods many\n");
+        let result = read_flat_keyed(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+        assert!(read_flat_keyed(&path).is_err());
+    }
+
+    #[test]
+    fn single_value() {
+        let path = write_file("pids.max", "max\n");
+        assert_eq!(read_value(&path).unwrap(), u64::MAX);
+        fs::write(&path, "42\n").unwrap();
+        assert_eq!(read_value(&path).unwrap(), 42);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn device_number() {
+        assert_eq!(parse_device("8:16").unwrap(), (8, 16));
+        assert!(parse_device("8").is_err());
+        assert!(parse_device("a:0").is_err());
+    }
+}
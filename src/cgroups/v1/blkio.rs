@@ -0,0 +1,122 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::cgroups::common;
+use crate::cgroups::stats::{self, BlkioEntry, BlkioStats};
+use crate::cgroups::v1::controller::Controller;
+use crate::spec::{LinuxBlockIO, LinuxResources, LinuxThrottleDevice};
+
+const CGROUP_BLKIO_WEIGHT: &str = "blkio.weight";
+const CGROUP_BLKIO_BFQ_WEIGHT: &str = "blkio.bfq.weight";
+const CGROUP_BLKIO_LEAF_WEIGHT: &str = "blkio.leaf_weight";
+const CGROUP_BLKIO_WEIGHT_DEVICE: &str = "blkio.weight_device";
+const CGROUP_BLKIO_LEAF_WEIGHT_DEVICE: &str = "blkio.leaf_weight_device";
+const CGROUP_BLKIO_THROTTLE_READ_BPS: &str = "blkio.throttle.read_bps_device";
+const CGROUP_BLKIO_THROTTLE_WRITE_BPS: &str = "blkio.throttle.write_bps_device";
+const CGROUP_BLKIO_THROTTLE_READ_IOPS: &str = "blkio.throttle.read_iops_device";
+const CGROUP_BLKIO_THROTTLE_WRITE_IOPS: &str = "blkio.throttle.write_iops_device";
+const CGROUP_BLKIO_THROTTLE_IO_SERVICE_BYTES: &str = "blkio.throttle.io_service_bytes";
+const CGROUP_BLKIO_THROTTLE_IO_SERVICED: &str = "blkio.throttle.io_serviced";
+
+pub struct Blkio {}
+
+impl Controller for Blkio {
+    fn apply(linux_resources: &LinuxResources, cgroup_path: &Path) -> Result<()> {
+        if let Some(block_io) = &linux_resources.block_io {
+            Self::apply(cgroup_path, block_io)?;
+        }
+        Ok(())
+    }
+}
+
+impl Blkio {
+    fn apply(cgroup_path: &Path, block_io: &LinuxBlockIO) -> Result<()> {
+        if let Some(weight) = block_io.blkio_weight {
+            // kernels using the BFQ scheduler only provide blkio.bfq.weight
+            let weight_file = if cgroup_path.join(CGROUP_BLKIO_WEIGHT).exists() {
+                CGROUP_BLKIO_WEIGHT
+            } else {
+                CGROUP_BLKIO_BFQ_WEIGHT
+            };
+            common::write_cgroup_file(cgroup_path.join(weight_file), &weight.to_string())?;
+        }
+        if let Some(leaf_weight) = block_io.blkio_leaf_weight {
+            common::write_cgroup_file(
+                cgroup_path.join(CGROUP_BLKIO_LEAF_WEIGHT),
+                &leaf_weight.to_string(),
+            )?;
+        }
+        for device in &block_io.blkio_weight_device {
+            if let Some(weight) = device.weight {
+                common::write_cgroup_file(
+                    cgroup_path.join(CGROUP_BLKIO_WEIGHT_DEVICE),
+                    &format!("{}:{} {}", device.major, device.minor, weight),
+                )?;
+            }
+            if let Some(leaf_weight) = device.leaf_weight {
+                common::write_cgroup_file(
+                    cgroup_path.join(CGROUP_BLKIO_LEAF_WEIGHT_DEVICE),
+                    &format!("{}:{} {}", device.major, device.minor, leaf_weight),
+                )?;
+            }
+        }
+
+        let throttles: [(&str, &Vec<LinuxThrottleDevice>); 4] = [
+            (
+                CGROUP_BLKIO_THROTTLE_READ_BPS,
+                &block_io.blkio_throttle_read_bps_device,
+            ),
+            (
+                CGROUP_BLKIO_THROTTLE_WRITE_BPS,
+                &block_io.blkio_throttle_write_bps_device,
+            ),
+            (
+                CGROUP_BLKIO_THROTTLE_READ_IOPS,
+                &block_io.blkio_throttle_read_iops_device,
+            ),
+            (
+                CGROUP_BLKIO_THROTTLE_WRITE_IOPS,
+                &block_io.blkio_throttle_write_iops_device,
+            ),
+        ];
+        for (file, devices) in throttles.iter() {
+            for device in devices.iter() {
+                common::write_cgroup_file(
+                    cgroup_path.join(file),
+                    &format!("{}:{} {}", device.major, device.minor, device.rate),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn stats(cgroup_path: &Path) -> Result<BlkioStats> {
+        Ok(BlkioStats {
+            io_service_bytes: Self::read_entries(
+                &cgroup_path.join(CGROUP_BLKIO_THROTTLE_IO_SERVICE_BYTES),
+            )?,
+            io_serviced: Self::read_entries(&cgroup_path.join(CGROUP_BLKIO_THROTTLE_IO_SERVICED))?,
+        })
+    }
+
+    // The lines are like "8:0 Read 4096", and end with a "Total" line.
+    fn read_entries(path: &Path) -> Result<Vec<BlkioEntry>> {
+        let mut entries = Vec::new();
+        for line in fs::read_to_string(path)?.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 3 {
+                continue;
+            }
+            let (major, minor) = stats::parse_device(fields[0])?;
+            entries.push(BlkioEntry {
+                major,
+                minor,
+                op: fields[1].to_string(),
+                value: fields[2].parse()?,
+            });
+        }
+        Ok(entries)
+    }
+}
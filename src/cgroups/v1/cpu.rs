@@ -0,0 +1,69 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::cgroups::common;
+use crate::cgroups::stats::{self, CpuThrottling};
+use crate::cgroups::v1::controller::Controller;
+use crate::spec::{LinuxCPU, LinuxResources};
+
+const CGROUP_CPU_SHARES: &str = "cpu.shares";
+const CGROUP_CPU_QUOTA: &str = "cpu.cfs_quota_us";
+const CGROUP_CPU_PERIOD: &str = "cpu.cfs_period_us";
+const CGROUP_CPU_RT_RUNTIME: &str = "cpu.rt_runtime_us";
+const CGROUP_CPU_RT_PERIOD: &str = "cpu.rt_period_us";
+const CGROUP_CPU_STAT: &str = "cpu.stat";
+
+pub struct Cpu {}
+
+impl Controller for Cpu {
+    fn apply(linux_resources: &LinuxResources, cgroup_path: &Path) -> Result<()> {
+        if let Some(cpu) = &linux_resources.cpu {
+            Self::apply(cgroup_path, cpu)?;
+        }
+        Ok(())
+    }
+}
+
+impl Cpu {
+    fn apply(cgroup_path: &Path, cpu: &LinuxCPU) -> Result<()> {
+        if let Some(shares) = cpu.shares {
+            if shares != 0 {
+                common::write_cgroup_file(
+                    cgroup_path.join(CGROUP_CPU_SHARES),
+                    &shares.to_string(),
+                )?;
+            }
+        }
+        if let Some(period) = cpu.period {
+            common::write_cgroup_file(cgroup_path.join(CGROUP_CPU_PERIOD), &period.to_string())?;
+        }
+        if let Some(quota) = cpu.quota {
+            common::write_cgroup_file(cgroup_path.join(CGROUP_CPU_QUOTA), &quota.to_string())?;
+        }
+        // the runtime can only be set within the new period
+        if let Some(rt_period) = cpu.realtime_period {
+            common::write_cgroup_file(
+                cgroup_path.join(CGROUP_CPU_RT_PERIOD),
+                &rt_period.to_string(),
+            )?;
+        }
+        if let Some(rt_runtime) = cpu.realtime_runtime {
+            common::write_cgroup_file(
+                cgroup_path.join(CGROUP_CPU_RT_RUNTIME),
+                &rt_runtime.to_string(),
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn stats(cgroup_path: &Path) -> Result<CpuThrottling> {
+        let stat = stats::read_flat_keyed(&cgroup_path.join(CGROUP_CPU_STAT))?;
+        let value = |key| stat.get(key).copied().unwrap_or(0);
+        Ok(CpuThrottling {
+            periods: value("nr_periods"),
+            throttled_periods: value("nr_throttled"),
+            throttled_time: value("throttled_time"),
+        })
+    }
+}
@@ -0,0 +1,31 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::cgroups::stats::{self, CpuUsage};
+
+const CGROUP_CPUACCT_USAGE: &str = "cpuacct.usage";
+const CGROUP_CPUACCT_USAGE_PERCPU: &str = "cpuacct.usage_percpu";
+const CGROUP_CPUACCT_USAGE_USER: &str = "cpuacct.usage_user";
+const CGROUP_CPUACCT_USAGE_SYS: &str = "cpuacct.usage_sys";
+
+// cpuacct only accounts for the cpu time, so it has no resources to apply.
+pub struct CpuAcct {}
+
+impl CpuAcct {
+    pub fn stats(cgroup_path: &Path) -> Result<CpuUsage> {
+        let mut per_cpu = Vec::new();
+        for usage in
+            fs::read_to_string(cgroup_path.join(CGROUP_CPUACCT_USAGE_PERCPU))?.split_whitespace()
+        {
+            per_cpu.push(usage.parse()?);
+        }
+        Ok(CpuUsage {
+            total: stats::read_value(&cgroup_path.join(CGROUP_CPUACCT_USAGE))?,
+            per_cpu,
+            kernel: stats::read_value(&cgroup_path.join(CGROUP_CPUACCT_USAGE_SYS))?,
+            user: stats::read_value(&cgroup_path.join(CGROUP_CPUACCT_USAGE_USER))?,
+        })
+    }
+}
@@ -0,0 +1,53 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+
+use crate::cgroups::common;
+use crate::cgroups::v1::controller::Controller;
+use crate::spec::{LinuxCPU, LinuxResources};
+
+const CGROUP_CPUSET_CPUS: &str = "cpuset.cpus";
+const CGROUP_CPUSET_MEMS: &str = "cpuset.mems";
+
+pub struct CpuSet {}
+
+impl Controller for CpuSet {
+    fn apply(linux_resources: &LinuxResources, cgroup_path: &Path) -> Result<()> {
+        // no process can join a cpuset without cpus or mems
+        Self::ensure_not_empty(cgroup_path, CGROUP_CPUSET_CPUS)?;
+        Self::ensure_not_empty(cgroup_path, CGROUP_CPUSET_MEMS)?;
+
+        if let Some(cpu) = &linux_resources.cpu {
+            Self::apply(cgroup_path, cpu)?;
+        }
+        Ok(())
+    }
+}
+
+impl CpuSet {
+    fn apply(cgroup_path: &Path, cpu: &LinuxCPU) -> Result<()> {
+        if !cpu.cpus.is_empty() {
+            common::write_cgroup_file(cgroup_path.join(CGROUP_CPUSET_CPUS), &cpu.cpus)?;
+        }
+        if !cpu.mems.is_empty() {
+            common::write_cgroup_file(cgroup_path.join(CGROUP_CPUSET_MEMS), &cpu.mems)?;
+        }
+        Ok(())
+    }
+
+    // A new cpuset cgroup starts out empty, so inherit the value of the closest ancestor.
+    fn ensure_not_empty(cgroup_path: &Path, file: &str) -> Result<()> {
+        let current = fs::read_to_string(cgroup_path.join(file))?;
+        if !current.trim().is_empty() {
+            return Ok(());
+        }
+        let parent = match cgroup_path.parent() {
+            Some(parent) => parent,
+            None => bail!("no ancestor of {:?} has {} set", cgroup_path, file),
+        };
+        Self::ensure_not_empty(parent, file)?;
+        let value = fs::read_to_string(parent.join(file))?;
+        common::write_cgroup_file(cgroup_path.join(file), value.trim())
+    }
+}
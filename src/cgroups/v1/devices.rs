@@ -0,0 +1,57 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+
+use crate::cgroups::common;
+use crate::cgroups::v1::controller::Controller;
+use crate::spec::{LinuxDeviceCgroup, LinuxDeviceType, LinuxResources};
+
+const CGROUP_DEVICES_ALLOW: &str = "devices.allow";
+const CGROUP_DEVICES_DENY: &str = "devices.deny";
+
+pub struct Devices {}
+
+impl Controller for Devices {
+    fn apply(linux_resources: &LinuxResources, cgroup_path: &Path) -> Result<()> {
+        // deny everything first, so that the rules decide what the container can access
+        common::write_cgroup_file(cgroup_path.join(CGROUP_DEVICES_DENY), "a")?;
+        for device in &linux_resources.devices {
+            Self::apply(cgroup_path, device)?;
+        }
+        Ok(())
+    }
+}
+
+impl Devices {
+    fn apply(cgroup_path: &Path, device: &LinuxDeviceCgroup) -> Result<()> {
+        let file = if device.allow {
+            CGROUP_DEVICES_ALLOW
+        } else {
+            CGROUP_DEVICES_DENY
+        };
+        common::write_cgroup_file(cgroup_path.join(file), &Self::to_rule(device)?)
+    }
+
+    // e.g. "c 1:3 rwm" or "a *:* rwm"
+    fn to_rule(device: &LinuxDeviceCgroup) -> Result<String> {
+        let typ = match device.typ {
+            LinuxDeviceType::A => 'a',
+            LinuxDeviceType::B => 'b',
+            LinuxDeviceType::C | LinuxDeviceType::U => 'c',
+            LinuxDeviceType::P => bail!("type p is not allowed for device cgroup rules"),
+        };
+        let number = |n: Option<i64>| n.map_or("*".to_string(), |n| n.to_string());
+        let access = if device.access.is_empty() {
+            "rwm"
+        } else {
+            device.access.as_str()
+        };
+        Ok(format!(
+            "{} {}:{} {}",
+            typ,
+            number(device.major),
+            number(device.minor),
+            access
+        ))
+    }
+}
@@ -0,0 +1,36 @@
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+
+use crate::cgroups::common::{self, FreezerState};
+
+const CGROUP_FREEZER_STATE: &str = "freezer.state";
+
+pub struct Freezer {}
+
+impl Freezer {
+    pub fn apply(state: FreezerState, cgroup_path: &Path) -> Result<()> {
+        let path = cgroup_path.join(CGROUP_FREEZER_STATE);
+        let target = match state {
+            FreezerState::Frozen => "FROZEN",
+            FreezerState::Thawed => "THAWED",
+        };
+        // The cgroup stays FREEZING while some tasks can't be frozen yet, writing FROZEN again
+        // retries them.
+        for _ in 0..1000 {
+            common::write_cgroup_file(&path, target)?;
+            if fs::read_to_string(&path)?.trim() == target {
+                return Ok(());
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        if state == FreezerState::Frozen {
+            // don't leave the container partly frozen
+            let _ = common::write_cgroup_file(&path, "THAWED");
+        }
+        bail!("timed out waiting for {:?} to be {}", cgroup_path, target)
+    }
+}
@@ -0,0 +1,46 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+
+use crate::cgroups::common;
+use crate::cgroups::stats::{self, HugeTlbStats};
+use crate::cgroups::v1::controller::Controller;
+use crate::spec::{LinuxHugepageLimit, LinuxResources};
+
+pub struct HugeTlb {}
+
+impl Controller for HugeTlb {
+    fn apply(linux_resources: &LinuxResources, cgroup_path: &Path) -> Result<()> {
+        for hugetlb in &linux_resources.hugepage_limits {
+            Self::apply(cgroup_path, hugetlb)?;
+        }
+        Ok(())
+    }
+}
+
+impl HugeTlb {
+    fn apply(cgroup_path: &Path, hugetlb: &LinuxHugepageLimit) -> Result<()> {
+        let file = cgroup_path.join(format!("hugetlb.{}.limit_in_bytes", hugetlb.page_size));
+        if !file.exists() {
+            bail!("hugepage size {} is not supported", hugetlb.page_size)
+        }
+        common::write_cgroup_file(file, &hugetlb.limit.to_string())
+    }
+
+    pub fn stats(cgroup_path: &Path) -> Result<HashMap<String, HugeTlbStats>> {
+        let mut sizes = HashMap::new();
+        for page_size in stats::hugepage_sizes()? {
+            let value = |name| {
+                stats::read_value(&cgroup_path.join(format!("hugetlb.{}.{}", page_size, name)))
+            };
+            let usage = HugeTlbStats {
+                usage: value("usage_in_bytes")?,
+                max_usage: value("max_usage_in_bytes")?,
+                failcnt: value("failcnt")?,
+            };
+            sizes.insert(page_size, usage);
+        }
+        Ok(sizes)
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use nix::unistd::Pid;
+use procfs::process::Process;
+
+use crate::cgroups::common::{self, CgroupManager, FreezerState, CGROUP_PROCS};
+use crate::cgroups::oom::OomNotifier;
+use crate::cgroups::stats::Stats;
+use crate::cgroups::v1::controller::Controller;
+use crate::cgroups::v1::{
+    blkio::Blkio, cpu::Cpu, cpuacct::CpuAcct, cpuset::CpuSet, devices::Devices, freezer::Freezer,
+    hugetlb::HugeTlb, memory::Memory, network_classifier::NetworkClassifier,
+    network_priority::NetworkPriority, pids::Pids,
+};
+use crate::spec::LinuxResources;
+
+const SUBSYSTEMS: [&str; 11] = [
+    "cpu", "cpuacct", "cpuset", "devices", "memory", "pids", "blkio", "hugetlb", "net_cls",
+    "net_prio", "freezer",
+];
+
+pub struct Manager {
+    // the cgroup of the container in the hierarchy of each mounted subsystem
+    subsystems: HashMap<String, PathBuf>,
+    // skip the hierarchies the user has no permission for
+    rootless: bool,
+}
+
+impl Manager {
+    pub fn new(cgroup_path: PathBuf, rootless: bool) -> Result<Self> {
+        let mut subsystems = HashMap::new();
+        for mount in Process::myself()?.mountinfo()? {
+            if mount.fs_type != "cgroup" {
+                continue;
+            }
+            for &subsystem in SUBSYSTEMS.iter() {
+                if mount.super_options.contains_key(subsystem) {
+                    subsystems.insert(
+                        subsystem.to_string(),
+                        common::join_cgroup_path(&mount.mount_point, &cgroup_path),
+                    );
+                }
+            }
+        }
+        log::debug!("cgroup v1 subsystems: {:?}", subsystems);
+        Ok(Self {
+            subsystems,
+            rootless,
+        })
+    }
+
+    // Apply the limits of a subsystem other than devices, whose rules are only set at creation.
+    fn apply_subsystem(
+        subsystem: &str,
+        linux_resources: &LinuxResources,
+        cgroup_path: &Path,
+    ) -> Result<()> {
+        match subsystem {
+            "cpu" => Cpu::apply(linux_resources, cgroup_path),
+            "cpuset" => CpuSet::apply(linux_resources, cgroup_path),
+            "memory" => Memory::apply(linux_resources, cgroup_path),
+            "pids" => Pids::apply(linux_resources, cgroup_path),
+            "blkio" => Blkio::apply(linux_resources, cgroup_path),
+            "hugetlb" => HugeTlb::apply(linux_resources, cgroup_path),
+            "net_cls" => NetworkClassifier::apply(linux_resources, cgroup_path),
+            "net_prio" => NetworkPriority::apply(linux_resources, cgroup_path),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_subsystems(&self, linux_resources: &LinuxResources) -> Result<()> {
+        let required = [
+            ("devices", !linux_resources.devices.is_empty()),
+            ("memory", linux_resources.memory.is_some()),
+            ("cpu", linux_resources.cpu.is_some()),
+            ("pids", linux_resources.pids.is_some()),
+            ("blkio", linux_resources.block_io.is_some()),
+            ("hugetlb", !linux_resources.hugepage_limits.is_empty()),
+            (
+                "net_cls",
+                matches!(&linux_resources.network, Some(network) if network.class_id.is_some()),
+            ),
+            (
+                "net_prio",
+                matches!(&linux_resources.network, Some(network) if !network.priorities.is_empty()),
+            ),
+        ];
+        for &(subsystem, is_required) in required.iter() {
+            if is_required && !self.subsystems.contains_key(subsystem) {
+                bail!("the cgroup subsystem {} is not mounted", subsystem);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CgroupManager for Manager {
+    fn apply(&self, linux_resources: &LinuxResources, pid: Pid) -> Result<()> {
+        self.check_subsystems(linux_resources)?;
+
+        for (subsystem, cgroup_path) in &self.subsystems {
+            if let Err(e) = fs::create_dir_all(cgroup_path) {
+                if self.rootless && e.kind() == ErrorKind::PermissionDenied {
+                    log::warn!(
+                        "skip the {} cgroup of a rootless container: {}",
+                        subsystem,
+                        e
+                    );
+                    continue;
+                }
+                return Err(e.into());
+            }
+            if subsystem == "devices" {
+                Devices::apply(linux_resources, cgroup_path)?;
+            } else {
+                Self::apply_subsystem(subsystem, linux_resources, cgroup_path)?;
+            }
+            common::write_cgroup_file(cgroup_path.join(CGROUP_PROCS), &pid.to_string())?;
+        }
+        Ok(())
+    }
+
+    fn update(&self, linux_resources: &LinuxResources) -> Result<()> {
+        self.check_subsystems(linux_resources)?;
+
+        for (subsystem, cgroup_path) in &self.subsystems {
+            // a rootless container goes without the hierarchies it may not write to
+            if !cgroup_path.exists() {
+                continue;
+            }
+            Self::apply_subsystem(subsystem, linux_resources, cgroup_path)?;
+        }
+        Ok(())
+    }
+
+    fn remove(&self) -> Result<()> {
+        for cgroup_path in self.subsystems.values() {
+            log::debug!("remove cgroup {:?}", cgroup_path);
+            common::remove_cgroup_dir(cgroup_path)?;
+        }
+        Ok(())
+    }
+
+    fn get_all_pids(&self) -> Result<Vec<Pid>> {
+        // every hierarchy holds the same processes
+        match SUBSYSTEMS
+            .iter()
+            .filter_map(|subsystem| self.subsystems.get(*subsystem))
+            .find(|cgroup_path| cgroup_path.exists())
+        {
+            Some(cgroup_path) => common::read_cgroup_pids(cgroup_path),
+            None => bail!("the cgroup does not exist"),
+        }
+    }
+
+    fn freeze(&self, state: FreezerState) -> Result<()> {
+        match self.subsystems.get("freezer") {
+            Some(cgroup_path) => Freezer::apply(state, cgroup_path),
+            None => bail!("the cgroup subsystem freezer is not mounted"),
+        }
+    }
+
+    fn freezer_path(&self) -> Result<PathBuf> {
+        match self.subsystems.get("freezer") {
+            Some(cgroup_path) => Ok(cgroup_path.clone()),
+            None => bail!("the cgroup subsystem freezer is not mounted"),
+        }
+    }
+
+    fn stats(&self) -> Result<Stats> {
+        let mut stats = Stats::default();
+        for (subsystem, cgroup_path) in &self.subsystems {
+            // a rootless container goes without the hierarchies it may not write to
+            if !cgroup_path.exists() {
+                continue;
+            }
+            match subsystem.as_str() {
+                "cpu" => stats.cpu.throttling = Cpu::stats(cgroup_path)?,
+                "cpuacct" => stats.cpu.usage = CpuAcct::stats(cgroup_path)?,
+                "memory" => stats.memory = Memory::stats(cgroup_path)?,
+                "pids" => stats.pids = Pids::stats(cgroup_path)?,
+                "blkio" => stats.blkio = Blkio::stats(cgroup_path)?,
+                "hugetlb" => stats.hugetlb = HugeTlb::stats(cgroup_path)?,
+                _ => {}
+            }
+        }
+        Ok(stats)
+    }
+
+    fn oom_notifier(&self) -> Result<OomNotifier> {
+        match self.subsystems.get("memory") {
+            Some(cgroup_path) => Memory::oom_notifier(cgroup_path),
+            None => bail!("the cgroup subsystem memory is not mounted"),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::fs::File;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+use anyhow::Result;
+use nix::sys::eventfd::{eventfd, EfdFlags};
+
+use crate::cgroups::common;
+use crate::cgroups::oom::OomNotifier;
+use crate::cgroups::stats::{self, MemoryData, MemoryStats};
+use crate::cgroups::v1::controller::Controller;
+use crate::spec::{LinuxMemory, LinuxResources};
+
+const CGROUP_MEMORY_LIMIT: &str = "memory.limit_in_bytes";
+const CGROUP_MEMORY_SWAP_LIMIT: &str = "memory.memsw.limit_in_bytes";
+const CGROUP_MEMORY_RESERVATION: &str = "memory.soft_limit_in_bytes";
+const CGROUP_KERNEL_MEMORY_LIMIT: &str = "memory.kmem.limit_in_bytes";
+const CGROUP_KERNEL_TCP_MEMORY_LIMIT: &str = "memory.kmem.tcp.limit_in_bytes";
+const CGROUP_MEMORY_SWAPPINESS: &str = "memory.swappiness";
+const CGROUP_MEMORY_OOM_CONTROL: &str = "memory.oom_control";
+const CGROUP_MEMORY_STAT: &str = "memory.stat";
+const CGROUP_EVENT_CONTROL: &str = "cgroup.event_control";
+
+pub struct Memory {}
+
+impl Controller for Memory {
+    fn apply(linux_resources: &LinuxResources, cgroup_path: &Path) -> Result<()> {
+        if let Some(memory) = &linux_resources.memory {
+            Self::apply(cgroup_path, memory)?;
+        }
+        // written either way, for an update to enable the OOM killer again
+        let oom_control = if linux_resources.disable_oom_killer {
+            "1"
+        } else {
+            "0"
+        };
+        common::write_cgroup_file(cgroup_path.join(CGROUP_MEMORY_OOM_CONTROL), oom_control)?;
+        Ok(())
+    }
+}
+
+impl Memory {
+    fn apply(cgroup_path: &Path, memory: &LinuxMemory) -> Result<()> {
+        // a swap of 0 leaves the swap unset, as with cgroup v2
+        let swap = memory.swap.filter(|&swap| swap != 0);
+        Self::set_limits(cgroup_path, memory.limit, swap)?;
+
+        if let Some(reservation) = memory.reservation {
+            common::write_cgroup_file(
+                cgroup_path.join(CGROUP_MEMORY_RESERVATION),
+                &reservation.to_string(),
+            )?;
+        }
+        if let Some(kernel) = memory.kernel {
+            common::write_cgroup_file(
+                cgroup_path.join(CGROUP_KERNEL_MEMORY_LIMIT),
+                &kernel.to_string(),
+            )?;
+        }
+        if let Some(kernel_tcp) = memory.kernel_tcp {
+            common::write_cgroup_file(
+                cgroup_path.join(CGROUP_KERNEL_TCP_MEMORY_LIMIT),
+                &kernel_tcp.to_string(),
+            )?;
+        }
+        if let Some(swappiness) = memory.swappiness {
+            common::write_cgroup_file(
+                cgroup_path.join(CGROUP_MEMORY_SWAPPINESS),
+                &swappiness.to_string(),
+            )?;
+        }
+        Ok(())
+    }
+
+    // The memory+swap limit can never be lower than the memory limit, so which one has to be
+    // written first depends on whether the limits are raised or lowered.
+    fn set_limits(cgroup_path: &Path, limit: Option<i64>, swap: Option<i64>) -> Result<()> {
+        let write_limit = || match limit {
+            Some(limit) => {
+                common::write_cgroup_file(cgroup_path.join(CGROUP_MEMORY_LIMIT), &limit.to_string())
+            }
+            None => Ok(()),
+        };
+        let write_swap = || match swap {
+            Some(swap) => common::write_cgroup_file(
+                cgroup_path.join(CGROUP_MEMORY_SWAP_LIMIT),
+                &swap.to_string(),
+            ),
+            None => Ok(()),
+        };
+
+        if write_limit().is_err() {
+            write_swap()?;
+            write_limit()?;
+        } else {
+            write_swap()?;
+        }
+        Ok(())
+    }
+
+    pub fn stats(cgroup_path: &Path) -> Result<MemoryStats> {
+        let usage = Self::memory_data(cgroup_path, "memory")?;
+        // memory.memsw only exists when the kernel accounts swap
+        let swap = if cgroup_path.join(CGROUP_MEMORY_SWAP_LIMIT).exists() {
+            Self::memory_data(cgroup_path, "memory.memsw")?
+        } else {
+            MemoryData::default()
+        };
+        Ok(MemoryStats {
+            usage,
+            swap,
+            stats: stats::read_flat_keyed(&cgroup_path.join(CGROUP_MEMORY_STAT))?,
+        })
+    }
+
+    fn memory_data(cgroup_path: &Path, prefix: &str) -> Result<MemoryData> {
+        let value = |name| stats::read_value(&cgroup_path.join(format!("{}.{}", prefix, name)));
+        Ok(MemoryData {
+            usage: value("usage_in_bytes")?,
+            max_usage: value("max_usage_in_bytes")?,
+            limit: value("limit_in_bytes")?,
+            failcnt: value("failcnt")?,
+        })
+    }
+
+    // Register an eventfd which the kernel signals on every OOM of the cgroup.
+    pub fn oom_notifier(cgroup_path: &Path) -> Result<OomNotifier> {
+        let oom_control = File::open(cgroup_path.join(CGROUP_MEMORY_OOM_CONTROL))?;
+        let oom_control_fd = oom_control.as_raw_fd();
+        let eventfd = eventfd(0, EfdFlags::EFD_CLOEXEC | EfdFlags::EFD_NONBLOCK)?;
+        let notifier = OomNotifier::EventFd {
+            eventfd,
+            oom_control,
+        };
+        common::write_cgroup_file(
+            cgroup_path.join(CGROUP_EVENT_CONTROL),
+            &format!("{} {}", eventfd, oom_control_fd),
+        )?;
+        Ok(notifier)
+    }
+}
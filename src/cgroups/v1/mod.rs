@@ -0,0 +1,14 @@
+mod blkio;
+mod controller;
+mod cpu;
+mod cpuacct;
+mod cpuset;
+mod devices;
+mod freezer;
+mod hugetlb;
+mod manager;
+mod memory;
+mod network_classifier;
+mod network_priority;
+mod pids;
+pub use manager::Manager;
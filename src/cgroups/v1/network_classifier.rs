@@ -0,0 +1,27 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::cgroups::common;
+use crate::cgroups::v1::controller::Controller;
+use crate::spec::LinuxResources;
+
+const CGROUP_NET_CLS_CLASSID: &str = "net_cls.classid";
+
+pub struct NetworkClassifier {}
+
+impl Controller for NetworkClassifier {
+    fn apply(linux_resources: &LinuxResources, cgroup_path: &Path) -> Result<()> {
+        if let Some(class_id) = linux_resources
+            .network
+            .as_ref()
+            .and_then(|network| network.class_id)
+        {
+            common::write_cgroup_file(
+                cgroup_path.join(CGROUP_NET_CLS_CLASSID),
+                &class_id.to_string(),
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,26 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::cgroups::common;
+use crate::cgroups::v1::controller::Controller;
+use crate::spec::LinuxResources;
+
+const CGROUP_NET_PRIO_IFPRIOMAP: &str = "net_prio.ifpriomap";
+
+pub struct NetworkPriority {}
+
+impl Controller for NetworkPriority {
+    fn apply(linux_resources: &LinuxResources, cgroup_path: &Path) -> Result<()> {
+        if let Some(network) = &linux_resources.network {
+            // the kernel takes one "<interface> <priority>" pair per write
+            for priority in &network.priorities {
+                common::write_cgroup_file(
+                    cgroup_path.join(CGROUP_NET_PRIO_IFPRIOMAP),
+                    &format!("{} {}", priority.name, priority.priority),
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
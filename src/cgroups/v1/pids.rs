@@ -0,0 +1,41 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::cgroups::common;
+use crate::cgroups::stats::{self, PidsStats};
+use crate::cgroups::v1::controller::Controller;
+use crate::spec::{LinuxPids, LinuxResources};
+
+const CGROUP_PIDS_MAX: &str = "pids.max";
+const CGROUP_PIDS_CURRENT: &str = "pids.current";
+
+pub struct Pids {}
+
+impl Controller for Pids {
+    fn apply(linux_resources: &LinuxResources, cgroup_path: &Path) -> Result<()> {
+        if let Some(pids) = &linux_resources.pids {
+            Self::apply(cgroup_path, pids)?;
+        }
+        Ok(())
+    }
+}
+
+impl Pids {
+    fn apply(cgroup_path: &Path, pids: &LinuxPids) -> Result<()> {
+        let limit = if pids.limit > 0 {
+            pids.limit.to_string()
+        } else {
+            "max".to_string()
+        };
+        common::write_cgroup_file(cgroup_path.join(CGROUP_PIDS_MAX), &limit)
+    }
+
+    pub fn stats(cgroup_path: &Path) -> Result<PidsStats> {
+        let limit = stats::read_value(&cgroup_path.join(CGROUP_PIDS_MAX))?;
+        Ok(PidsStats {
+            current: stats::read_value(&cgroup_path.join(CGROUP_PIDS_CURRENT))?,
+            limit: if limit == u64::MAX { 0 } else { limit },
+        })
+    }
+}
@@ -0,0 +1,132 @@
+use std::mem;
+use std::os::unix::io::RawFd;
+
+use anyhow::{bail, Result};
+use nix::errno::Errno;
+
+const BPF_PROG_LOAD: libc::c_long = 5;
+const BPF_PROG_ATTACH: libc::c_long = 8;
+const BPF_PROG_TYPE_CGROUP_DEVICE: u32 = 15;
+const BPF_CGROUP_DEVICE: u32 = 6;
+const BPF_F_ALLOW_MULTI: u32 = 2;
+
+// struct bpf_insn
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Instruction {
+    code: u8,
+    // the destination register in the low nibble, the source register in the high one
+    regs: u8,
+    off: i16,
+    imm: i32,
+}
+
+impl Instruction {
+    pub fn new(code: u8, dst: u8, src: u8, off: i16, imm: i32) -> Self {
+        Self {
+            code,
+            regs: (src << 4) | (dst & 0x0f),
+            off,
+            imm,
+        }
+    }
+
+    pub fn set_offset(&mut self, off: i16) {
+        self.off = off;
+    }
+}
+
+// The part of union bpf_attr used by BPF_PROG_LOAD.
+#[repr(C)]
+#[derive(Default)]
+struct ProgLoadAttr {
+    prog_type: u32,
+    insn_cnt: u32,
+    insns: u64,
+    license: u64,
+    log_level: u32,
+    log_size: u32,
+    log_buf: u64,
+    kern_version: u32,
+    prog_flags: u32,
+}
+
+// The part of union bpf_attr used by BPF_PROG_ATTACH.
+#[repr(C)]
+#[derive(Default)]
+struct ProgAttachAttr {
+    target_fd: u32,
+    attach_bpf_fd: u32,
+    attach_type: u32,
+    attach_flags: u32,
+}
+
+pub fn prog_load_cgroup_device(insns: &[Instruction]) -> Result<RawFd> {
+    // the verifier log may not fit in the buffer of a large program, so it is only asked for to
+    // explain why the load failed
+    let errno = match prog_load(insns, &mut []) {
+        Ok(fd) => return Ok(fd),
+        Err(errno) => errno,
+    };
+    let mut log = vec![0u8; 64 * 1024];
+    if let Ok(fd) = prog_load(insns, &mut log) {
+        return Ok(fd);
+    }
+    let len = log.iter().position(|&b| b == 0).unwrap_or(log.len());
+    bail!(
+        "failed to load the device filter: {}: {}",
+        errno,
+        String::from_utf8_lossy(&log[..len]).trim_end()
+    );
+}
+
+fn prog_load(insns: &[Instruction], log: &mut [u8]) -> std::result::Result<RawFd, Errno> {
+    let license = b"Apache\0";
+    let attr = ProgLoadAttr {
+        prog_type: BPF_PROG_TYPE_CGROUP_DEVICE,
+        insn_cnt: insns.len() as u32,
+        insns: insns.as_ptr() as u64,
+        license: license.as_ptr() as u64,
+        log_level: if log.is_empty() { 0 } else { 1 },
+        log_size: log.len() as u32,
+        log_buf: if log.is_empty() {
+            0
+        } else {
+            log.as_mut_ptr() as u64
+        },
+        ..Default::default()
+    };
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_bpf,
+            BPF_PROG_LOAD,
+            &attr as *const ProgLoadAttr,
+            mem::size_of::<ProgLoadAttr>(),
+        )
+    };
+    if fd < 0 {
+        return Err(Errno::last());
+    }
+    Ok(fd as RawFd)
+}
+
+pub fn prog_attach_cgroup_device(prog_fd: RawFd, cgroup_fd: RawFd) -> Result<()> {
+    let attr = ProgAttachAttr {
+        target_fd: cgroup_fd as u32,
+        attach_bpf_fd: prog_fd as u32,
+        attach_type: BPF_CGROUP_DEVICE,
+        attach_flags: BPF_F_ALLOW_MULTI,
+    };
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_bpf,
+            BPF_PROG_ATTACH,
+            &attr as *const ProgAttachAttr,
+            mem::size_of::<ProgAttachAttr>(),
+        )
+    };
+    if ret < 0 {
+        bail!("failed to attach the device filter: {}", Errno::last());
+    }
+    Ok(())
+}
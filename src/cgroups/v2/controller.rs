@@ -0,0 +1,9 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::spec::LinuxResources;
+
+pub trait Controller {
+    fn apply(linux_resources: &LinuxResources, cgroup_path: &Path) -> Result<()>;
+}
@@ -0,0 +1,83 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::cgroups::common;
+use crate::cgroups::stats::{self, CpuStats, CpuThrottling, CpuUsage};
+use crate::cgroups::v2::controller::Controller;
+use crate::spec::{LinuxCPU, LinuxResources};
+
+const CGROUP_CPU_WEIGHT: &str = "cpu.weight";
+const CGROUP_CPU_MAX: &str = "cpu.max";
+const CGROUP_CPU_STAT: &str = "cpu.stat";
+const UNRESTRICTED_QUOTA: &str = "max";
+
+pub struct Cpu {}
+
+impl Controller for Cpu {
+    fn apply(linux_resources: &LinuxResources, cgroup_path: &Path) -> Result<()> {
+        if let Some(cpu) = &linux_resources.cpu {
+            Self::apply(cgroup_path, cpu)?;
+        }
+        Ok(())
+    }
+}
+
+impl Cpu {
+    fn apply(cgroup_path: &Path, cpu: &LinuxCPU) -> Result<()> {
+        if let Some(shares) = cpu.shares {
+            if shares != 0 {
+                common::write_cgroup_file(
+                    cgroup_path.join(CGROUP_CPU_WEIGHT),
+                    &Self::convert_shares_to_weight(shares).to_string(),
+                )?;
+            }
+        }
+
+        // cpu.max holds "$QUOTA $PERIOD", where the period can be omitted to keep its value
+        let quota = match cpu.quota {
+            Some(quota) if quota > 0 => Some(quota.to_string()),
+            Some(_) => Some(UNRESTRICTED_QUOTA.to_string()),
+            None => None,
+        };
+        let max = match (quota, cpu.period) {
+            (Some(quota), Some(period)) => Some(format!("{} {}", quota, period)),
+            (Some(quota), None) => Some(quota),
+            (None, Some(period)) => Some(format!("{} {}", UNRESTRICTED_QUOTA, period)),
+            (None, None) => None,
+        };
+        if let Some(max) = max {
+            common::write_cgroup_file(cgroup_path.join(CGROUP_CPU_MAX), &max)?;
+        }
+
+        if cpu.realtime_runtime.is_some() || cpu.realtime_period.is_some() {
+            log::warn!("realtime scheduling is not supported by cgroup v2");
+        }
+        Ok(())
+    }
+
+    // Map cpu.shares of cgroup v1, [2, 262144], onto cpu.weight, [1, 10000].
+    fn convert_shares_to_weight(shares: u64) -> u64 {
+        let shares = shares.clamp(2, 262144);
+        1 + ((shares - 2) * 9999) / 262142
+    }
+
+    // cpu.stat is there even when the cpu controller isn't enabled, and reports microseconds.
+    pub fn stats(cgroup_path: &Path) -> Result<CpuStats> {
+        let stat = stats::read_flat_keyed(&cgroup_path.join(CGROUP_CPU_STAT))?;
+        let nanos = |key| stat.get(key).copied().unwrap_or(0) * 1000;
+        Ok(CpuStats {
+            usage: CpuUsage {
+                total: nanos("usage_usec"),
+                per_cpu: Vec::new(),
+                kernel: nanos("system_usec"),
+                user: nanos("user_usec"),
+            },
+            throttling: CpuThrottling {
+                periods: stat.get("nr_periods").copied().unwrap_or(0),
+                throttled_periods: stat.get("nr_throttled").copied().unwrap_or(0),
+                throttled_time: nanos("throttled_usec"),
+            },
+        })
+    }
+}
@@ -0,0 +1,33 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::cgroups::common;
+use crate::cgroups::v2::controller::Controller;
+use crate::spec::{LinuxCPU, LinuxResources};
+
+const CGROUP_CPUSET_CPUS: &str = "cpuset.cpus";
+const CGROUP_CPUSET_MEMS: &str = "cpuset.mems";
+
+pub struct CpuSet {}
+
+impl Controller for CpuSet {
+    fn apply(linux_resources: &LinuxResources, cgroup_path: &Path) -> Result<()> {
+        if let Some(cpu) = &linux_resources.cpu {
+            Self::apply(cgroup_path, cpu)?;
+        }
+        Ok(())
+    }
+}
+
+impl CpuSet {
+    fn apply(cgroup_path: &Path, cpu: &LinuxCPU) -> Result<()> {
+        if !cpu.cpus.is_empty() {
+            common::write_cgroup_file(cgroup_path.join(CGROUP_CPUSET_CPUS), &cpu.cpus)?;
+        }
+        if !cpu.mems.is_empty() {
+            common::write_cgroup_file(cgroup_path.join(CGROUP_CPUSET_MEMS), &cpu.mems)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use nix::fcntl::{self, OFlag};
+use nix::sys::stat::Mode;
+use nix::unistd;
+
+use crate::cgroups::v2::bpf::{self, Instruction};
+use crate::cgroups::v2::controller::Controller;
+use crate::spec::{LinuxDeviceCgroup, LinuxDeviceType, LinuxResources};
+
+// registers of the eBPF virtual machine
+const R0: u8 = 0;
+const R1: u8 = 1;
+const R2: u8 = 2;
+const R3: u8 = 3;
+const R4: u8 = 4;
+const R5: u8 = 5;
+
+// opcodes
+const LDX_MEM_W: u8 = 0x61;
+const ALU32_AND_K: u8 = 0x54;
+const ALU32_RSH_K: u8 = 0x74;
+const ALU32_MOV_X: u8 = 0xbc;
+const ALU64_MOV_K: u8 = 0xb7;
+const JMP_JNE_K: u8 = 0x55;
+const JMP_JNE_X: u8 = 0x5d;
+const JMP_EXIT: u8 = 0x95;
+
+// values of struct bpf_cgroup_dev_ctx
+const BPF_DEVCG_DEV_BLOCK: i32 = 1;
+const BPF_DEVCG_DEV_CHAR: i32 = 2;
+const BPF_DEVCG_ACC_MKNOD: i32 = 1;
+const BPF_DEVCG_ACC_READ: i32 = 2;
+const BPF_DEVCG_ACC_WRITE: i32 = 4;
+
+// cgroup v2 has no devices controller: access to devices is decided by an eBPF program
+// attached to the cgroup.
+pub struct Devices {}
+
+impl Controller for Devices {
+    fn apply(linux_resources: &LinuxResources, cgroup_path: &Path) -> Result<()> {
+        if linux_resources.devices.is_empty() {
+            return Ok(());
+        }
+        let program = Self::build_program(&linux_resources.devices)?;
+        log::debug!("device filter: {} instructions", program.len());
+
+        let prog_fd = bpf::prog_load_cgroup_device(&program)?;
+        let cgroup_fd = fcntl::open(
+            cgroup_path,
+            OFlag::O_RDONLY | OFlag::O_DIRECTORY,
+            Mode::empty(),
+        )?;
+        let result = bpf::prog_attach_cgroup_device(prog_fd, cgroup_fd);
+        // the program stays attached after its fd is closed
+        unistd::close(cgroup_fd)?;
+        unistd::close(prog_fd)?;
+        result
+    }
+}
+
+impl Devices {
+    // As with the devices controller of cgroup v1, the last rule matching an access decides
+    // it, and accesses matching no rule are denied.
+    fn build_program(rules: &[LinuxDeviceCgroup]) -> Result<Vec<Instruction>> {
+        let mut program = vec![
+            // r2 = type, r3 = access, r4 = major, r5 = minor
+            Instruction::new(LDX_MEM_W, R2, R1, 0, 0),
+            Instruction::new(ALU32_AND_K, R2, 0, 0, 0xffff),
+            Instruction::new(LDX_MEM_W, R3, R1, 0, 0),
+            Instruction::new(ALU32_RSH_K, R3, 0, 0, 16),
+            Instruction::new(LDX_MEM_W, R4, R1, 4, 0),
+            Instruction::new(LDX_MEM_W, R5, R1, 8, 0),
+        ];
+        for rule in rules.iter().rev() {
+            let (block, matches_all) = Self::build_rule(rule)?;
+            program.extend(block);
+            // the verifier rejects unreachable instructions
+            if matches_all {
+                return Ok(program);
+            }
+        }
+        program.push(Instruction::new(ALU64_MOV_K, R0, 0, 0, 0));
+        program.push(Instruction::new(JMP_EXIT, 0, 0, 0, 0));
+        Ok(program)
+    }
+
+    // Each check jumps to the end of the block when the access does not match the rule.
+    // Also tells whether the rule matches every access.
+    fn build_rule(rule: &LinuxDeviceCgroup) -> Result<(Vec<Instruction>, bool)> {
+        let mut block = Vec::new();
+        let mut jumps = Vec::new();
+
+        let typ = match rule.typ {
+            LinuxDeviceType::A => None,
+            LinuxDeviceType::B => Some(BPF_DEVCG_DEV_BLOCK),
+            LinuxDeviceType::C | LinuxDeviceType::U => Some(BPF_DEVCG_DEV_CHAR),
+            LinuxDeviceType::P => bail!("type p is not allowed for device cgroup rules"),
+        };
+        if let Some(typ) = typ {
+            jumps.push(block.len());
+            block.push(Instruction::new(JMP_JNE_K, R2, 0, 0, typ));
+        }
+
+        let access = Self::to_access(&rule.access)?;
+        let all_access = BPF_DEVCG_ACC_MKNOD | BPF_DEVCG_ACC_READ | BPF_DEVCG_ACC_WRITE;
+        if access != all_access {
+            // the requested access has to be a subset of the rule
+            block.push(Instruction::new(ALU32_MOV_X, R1, R3, 0, 0));
+            block.push(Instruction::new(ALU32_AND_K, R1, 0, 0, access));
+            jumps.push(block.len());
+            block.push(Instruction::new(JMP_JNE_X, R1, R3, 0, 0));
+        }
+
+        for &(reg, number) in [(R4, rule.major), (R5, rule.minor)].iter() {
+            if let Some(number) = number {
+                if number >= 0 {
+                    jumps.push(block.len());
+                    block.push(Instruction::new(JMP_JNE_K, reg, 0, 0, number as i32));
+                }
+            }
+        }
+
+        block.push(Instruction::new(ALU64_MOV_K, R0, 0, 0, rule.allow as i32));
+        block.push(Instruction::new(JMP_EXIT, 0, 0, 0, 0));
+
+        let len = block.len();
+        let matches_all = jumps.is_empty();
+        for i in jumps {
+            block[i].set_offset((len - i - 1) as i16);
+        }
+        Ok((block, matches_all))
+    }
+
+    fn to_access(access: &str) -> Result<i32> {
+        if access.is_empty() {
+            return Ok(BPF_DEVCG_ACC_MKNOD | BPF_DEVCG_ACC_READ | BPF_DEVCG_ACC_WRITE);
+        }
+        let mut bits = 0;
+        for c in access.chars() {
+            bits |= match c {
+                'r' => BPF_DEVCG_ACC_READ,
+                'w' => BPF_DEVCG_ACC_WRITE,
+                'm' => BPF_DEVCG_ACC_MKNOD,
+                _ => bail!("invalid device access {}", access),
+            };
+        }
+        Ok(bits)
+    }
+}
@@ -0,0 +1,38 @@
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+
+use crate::cgroups::common::{self, FreezerState};
+
+const CGROUP_FREEZE: &str = "cgroup.freeze";
+const CGROUP_EVENTS: &str = "cgroup.events";
+
+pub struct Freezer {}
+
+impl Freezer {
+    pub fn apply(state: FreezerState, cgroup_path: &Path) -> Result<()> {
+        let value = match state {
+            FreezerState::Frozen => "1",
+            FreezerState::Thawed => "0",
+        };
+        common::write_cgroup_file(cgroup_path.join(CGROUP_FREEZE), value)?;
+
+        // the frozen field of cgroup.events changes once every process has stopped or resumed
+        let expected = format!("frozen {}", value);
+        for _ in 0..1000 {
+            let events = fs::read_to_string(cgroup_path.join(CGROUP_EVENTS))?;
+            if events.lines().any(|line| line == expected) {
+                return Ok(());
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        if state == FreezerState::Frozen {
+            // don't leave the container partly frozen
+            let _ = common::write_cgroup_file(cgroup_path.join(CGROUP_FREEZE), "0");
+        }
+        bail!("timed out waiting for {:?} to be {:?}", cgroup_path, state)
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+
+use crate::cgroups::common;
+use crate::cgroups::stats::{self, HugeTlbStats};
+use crate::cgroups::v2::controller::Controller;
+use crate::spec::{LinuxHugepageLimit, LinuxResources};
+
+pub struct HugeTlb {}
+
+impl Controller for HugeTlb {
+    fn apply(linux_resources: &LinuxResources, cgroup_path: &Path) -> Result<()> {
+        for hugetlb in &linux_resources.hugepage_limits {
+            Self::apply(cgroup_path, hugetlb)?;
+        }
+        Ok(())
+    }
+}
+
+impl HugeTlb {
+    fn apply(cgroup_path: &Path, hugetlb: &LinuxHugepageLimit) -> Result<()> {
+        // page sizes are given as in the file names of the controller, e.g. 2MB or 1GB
+        let page_size = &hugetlb.page_size;
+        let valid = ["KB", "MB", "GB"].iter().any(|unit| {
+            page_size
+                .strip_suffix(unit)
+                .is_some_and(|size| size.parse::<u64>().is_ok())
+        });
+        if !valid {
+            bail!("invalid hugepage size {}", page_size)
+        }
+        common::write_cgroup_file(
+            cgroup_path.join(format!("hugetlb.{}.max", page_size)),
+            &hugetlb.limit.to_string(),
+        )
+    }
+
+    pub fn stats(cgroup_path: &Path) -> Result<HashMap<String, HugeTlbStats>> {
+        let mut sizes = HashMap::new();
+        for page_size in stats::hugepage_sizes()? {
+            let file = |name| cgroup_path.join(format!("hugetlb.{}.{}", page_size, name));
+            let events = stats::read_flat_keyed(&file("events"))?;
+            let usage = HugeTlbStats {
+                usage: stats::read_value(&file("current"))?,
+                max_usage: 0,
+                failcnt: events.get("max").copied().unwrap_or(0),
+            };
+            sizes.insert(page_size, usage);
+        }
+        Ok(sizes)
+    }
+}
@@ -0,0 +1,109 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::cgroups::common;
+use crate::cgroups::stats::{self, BlkioEntry, BlkioStats};
+use crate::cgroups::v2::controller::Controller;
+use crate::spec::{LinuxBlockIO, LinuxResources, LinuxThrottleDevice};
+
+const CGROUP_IO_WEIGHT: &str = "io.weight";
+const CGROUP_IO_MAX: &str = "io.max";
+const CGROUP_IO_STAT: &str = "io.stat";
+
+pub struct Io {}
+
+impl Controller for Io {
+    fn apply(linux_resources: &LinuxResources, cgroup_path: &Path) -> Result<()> {
+        if let Some(block_io) = &linux_resources.block_io {
+            Self::apply(cgroup_path, block_io)?;
+        }
+        Ok(())
+    }
+}
+
+impl Io {
+    fn apply(cgroup_path: &Path, block_io: &LinuxBlockIO) -> Result<()> {
+        if let Some(weight) = block_io.blkio_weight {
+            if weight != 0 {
+                common::write_cgroup_file(
+                    cgroup_path.join(CGROUP_IO_WEIGHT),
+                    &Self::convert_weight(weight).to_string(),
+                )?;
+            }
+        }
+        for device in &block_io.blkio_weight_device {
+            if let Some(weight) = device.weight {
+                common::write_cgroup_file(
+                    cgroup_path.join(CGROUP_IO_WEIGHT),
+                    &format!(
+                        "{}:{} {}",
+                        device.major,
+                        device.minor,
+                        Self::convert_weight(weight)
+                    ),
+                )?;
+            }
+        }
+        if block_io.blkio_leaf_weight.is_some() {
+            log::warn!("blkio leaf weight is not supported by cgroup v2");
+        }
+
+        let throttles: [(&str, &Vec<LinuxThrottleDevice>); 4] = [
+            ("rbps", &block_io.blkio_throttle_read_bps_device),
+            ("wbps", &block_io.blkio_throttle_write_bps_device),
+            ("riops", &block_io.blkio_throttle_read_iops_device),
+            ("wiops", &block_io.blkio_throttle_write_iops_device),
+        ];
+        for (key, devices) in throttles.iter() {
+            for device in devices.iter() {
+                common::write_cgroup_file(
+                    cgroup_path.join(CGROUP_IO_MAX),
+                    &format!("{}:{} {}={}", device.major, device.minor, key, device.rate),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    // Map blkio.weight of cgroup v1, [10, 1000], onto io.weight, [1, 10000].
+    fn convert_weight(weight: u16) -> u64 {
+        let weight = u64::from(weight.clamp(10, 1000));
+        1 + ((weight - 10) * 9999) / 990
+    }
+
+    // The lines are like "8:0 rbytes=4096 wbytes=0 rios=1 wios=0 dbytes=0 dios=0".
+    pub fn stats(cgroup_path: &Path) -> Result<BlkioStats> {
+        let mut stats = BlkioStats::default();
+        for line in fs::read_to_string(cgroup_path.join(CGROUP_IO_STAT))?.lines() {
+            let mut fields = line.split_whitespace();
+            let (major, minor) = match fields.next() {
+                Some(device) => stats::parse_device(device)?,
+                None => continue,
+            };
+            for field in fields {
+                let (key, value) = match field.split_once('=') {
+                    Some((key, value)) => (key, value.parse()?),
+                    None => continue,
+                };
+                let (entries, op) = match key {
+                    "rbytes" => (&mut stats.io_service_bytes, "Read"),
+                    "wbytes" => (&mut stats.io_service_bytes, "Write"),
+                    "dbytes" => (&mut stats.io_service_bytes, "Discard"),
+                    "rios" => (&mut stats.io_serviced, "Read"),
+                    "wios" => (&mut stats.io_serviced, "Write"),
+                    "dios" => (&mut stats.io_serviced, "Discard"),
+                    _ => continue,
+                };
+                entries.push(BlkioEntry {
+                    major,
+                    minor,
+                    op: op.to_string(),
+                    value,
+                });
+            }
+        }
+        Ok(stats)
+    }
+}
@@ -0,0 +1,140 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+
+use anyhow::Result;
+use nix::unistd::Pid;
+
+use crate::cgroups::common::{self, CgroupManager, FreezerState, CGROUP_PROCS};
+use crate::cgroups::oom::OomNotifier;
+use crate::cgroups::stats::Stats;
+use crate::cgroups::v2::controller::Controller;
+use crate::cgroups::v2::{
+    cpu::Cpu, cpuset::CpuSet, devices::Devices, freezer::Freezer, hugetlb::HugeTlb, io::Io,
+    memory::Memory, pids::Pids,
+};
+use crate::spec::LinuxResources;
+
+const CGROUP_CONTROLLERS: &str = "cgroup.controllers";
+const CGROUP_SUBTREE_CONTROL: &str = "cgroup.subtree_control";
+
+pub struct Manager {
+    root_path: PathBuf,
+    cgroup_path: PathBuf,
+    full_path: PathBuf,
+    // go without the cgroup when the user has no permission to create it
+    rootless: bool,
+}
+
+impl Manager {
+    pub fn new(root_path: PathBuf, cgroup_path: PathBuf, rootless: bool) -> Self {
+        let full_path = common::join_cgroup_path(&root_path, &cgroup_path);
+        Self {
+            root_path,
+            cgroup_path,
+            full_path,
+            rootless,
+        }
+    }
+
+    // Create every missing cgroup on the way down from the root, delegating all available
+    // controllers to the children of each level.
+    fn create_unified_cgroup(&self) -> Result<()> {
+        let controllers = fs::read_to_string(self.root_path.join(CGROUP_CONTROLLERS))?;
+        let relative_path = self
+            .cgroup_path
+            .strip_prefix("/")
+            .unwrap_or(&self.cgroup_path);
+        let mut current = self.root_path.clone();
+        for component in relative_path.components() {
+            for controller in controllers.split_whitespace() {
+                let subtree_control = current.join(CGROUP_SUBTREE_CONTROL);
+                if let Err(e) =
+                    common::write_cgroup_file(subtree_control, &format!("+{}", controller))
+                {
+                    log::warn!("could not enable the {} controller: {}", controller, e);
+                }
+            }
+            current = current.join(component);
+            if !current.exists() {
+                fs::create_dir(&current)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CgroupManager for Manager {
+    fn apply(&self, linux_resources: &LinuxResources, pid: Pid) -> Result<()> {
+        if let Err(e) = self.create_unified_cgroup() {
+            let denied = matches!(e.downcast_ref::<io::Error>(),
+                Some(e) if e.kind() == ErrorKind::PermissionDenied);
+            if self.rootless && denied {
+                log::warn!("skip the cgroup of a rootless container: {}", e);
+                return Ok(());
+            }
+            return Err(e);
+        }
+
+        self.update(linux_resources)?;
+        // Only root can attach the eBPF program, the user namespace already keeps a rootless
+        // container from creating devices.
+        if !self.rootless {
+            Devices::apply(linux_resources, &self.full_path)?;
+        }
+
+        common::write_cgroup_file(self.full_path.join(CGROUP_PROCS), &pid.to_string())?;
+        Ok(())
+    }
+
+    // The device rules are left out: each application attaches one more eBPF program.
+    fn update(&self, linux_resources: &LinuxResources) -> Result<()> {
+        Cpu::apply(linux_resources, &self.full_path)?;
+        CpuSet::apply(linux_resources, &self.full_path)?;
+        Memory::apply(linux_resources, &self.full_path)?;
+        Pids::apply(linux_resources, &self.full_path)?;
+        Io::apply(linux_resources, &self.full_path)?;
+        HugeTlb::apply(linux_resources, &self.full_path)?;
+        Ok(())
+    }
+
+    fn remove(&self) -> Result<()> {
+        log::debug!("remove cgroup {:?}", self.full_path);
+        common::remove_cgroup_dir(&self.full_path)
+    }
+
+    fn get_all_pids(&self) -> Result<Vec<Pid>> {
+        common::read_cgroup_pids(&self.full_path)
+    }
+
+    fn freeze(&self, state: FreezerState) -> Result<()> {
+        Freezer::apply(state, &self.full_path)
+    }
+
+    fn freezer_path(&self) -> Result<PathBuf> {
+        Ok(self.full_path.clone())
+    }
+
+    fn stats(&self) -> Result<Stats> {
+        let mut stats = Stats {
+            cpu: Cpu::stats(&self.full_path)?,
+            ..Default::default()
+        };
+        // the files of the other controllers only exist when they are enabled
+        let controllers = fs::read_to_string(self.full_path.join(CGROUP_CONTROLLERS))?;
+        for controller in controllers.split_whitespace() {
+            match controller {
+                "memory" => stats.memory = Memory::stats(&self.full_path)?,
+                "pids" => stats.pids = Pids::stats(&self.full_path)?,
+                "io" => stats.blkio = Io::stats(&self.full_path)?,
+                "hugetlb" => stats.hugetlb = HugeTlb::stats(&self.full_path)?,
+                _ => {}
+            }
+        }
+        Ok(stats)
+    }
+
+    fn oom_notifier(&self) -> Result<OomNotifier> {
+        Memory::oom_notifier(&self.full_path)
+    }
+}
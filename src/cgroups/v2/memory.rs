@@ -0,0 +1,124 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify};
+
+use crate::cgroups::common;
+use crate::cgroups::oom::OomNotifier;
+use crate::cgroups::stats::{self, MemoryData, MemoryStats};
+use crate::cgroups::v2::controller::Controller;
+use crate::spec::{LinuxMemory, LinuxResources};
+
+const CGROUP_MEMORY_MAX: &str = "memory.max";
+const CGROUP_MEMORY_LOW: &str = "memory.low";
+const CGROUP_MEMORY_SWAP_MAX: &str = "memory.swap.max";
+const CGROUP_MEMORY_CURRENT: &str = "memory.current";
+const CGROUP_MEMORY_PEAK: &str = "memory.peak";
+const CGROUP_MEMORY_SWAP_CURRENT: &str = "memory.swap.current";
+const CGROUP_MEMORY_EVENTS: &str = "memory.events";
+const CGROUP_MEMORY_STAT: &str = "memory.stat";
+const UNLIMITED: &str = "max";
+
+pub struct Memory {}
+
+impl Controller for Memory {
+    fn apply(linux_resources: &LinuxResources, cgroup_path: &Path) -> Result<()> {
+        if let Some(memory) = &linux_resources.memory {
+            Self::apply(cgroup_path, memory)?;
+        }
+        Ok(())
+    }
+}
+
+impl Memory {
+    fn apply(cgroup_path: &Path, memory: &LinuxMemory) -> Result<()> {
+        if let Some(limit) = memory.limit {
+            common::write_cgroup_file(cgroup_path.join(CGROUP_MEMORY_MAX), &Self::to_max(limit)?)?;
+        }
+
+        // The spec limits memory and swap together, but memory.swap.max only limits the swap.
+        // A swap of 0 leaves the swap unset.
+        if let Some(swap) = memory.swap.filter(|&swap| swap != 0) {
+            let swap_max = match (swap, memory.limit) {
+                (-1, _) => UNLIMITED.to_string(),
+                (swap, Some(limit)) if limit > 0 => {
+                    if swap < limit {
+                        bail!(
+                            "memory+swap limit {} must not be lower than the memory limit {}",
+                            swap,
+                            limit
+                        );
+                    }
+                    (swap - limit).to_string()
+                }
+                _ => bail!("a swap limit can only be set together with a memory limit"),
+            };
+            common::write_cgroup_file(cgroup_path.join(CGROUP_MEMORY_SWAP_MAX), &swap_max)?;
+        }
+
+        if let Some(reservation) = memory.reservation {
+            common::write_cgroup_file(
+                cgroup_path.join(CGROUP_MEMORY_LOW),
+                &Self::to_max(reservation)?,
+            )?;
+        }
+
+        if memory.kernel.is_some() || memory.kernel_tcp.is_some() {
+            log::warn!("kernel memory limits are not supported by cgroup v2");
+        }
+        if memory.swappiness.is_some() {
+            log::warn!("memory swappiness is not supported by cgroup v2");
+        }
+        Ok(())
+    }
+
+    fn to_max(value: i64) -> Result<String> {
+        match value {
+            -1 => Ok(UNLIMITED.to_string()),
+            value if value < 0 => bail!("invalid memory value {}", value),
+            value => Ok(value.to_string()),
+        }
+    }
+
+    pub fn stats(cgroup_path: &Path) -> Result<MemoryStats> {
+        let value = |name| stats::read_value(&cgroup_path.join(name));
+        let events = stats::read_flat_keyed(&cgroup_path.join(CGROUP_MEMORY_EVENTS))?;
+        let usage = MemoryData {
+            usage: value(CGROUP_MEMORY_CURRENT)?,
+            // memory.peak only exists on recent kernels
+            max_usage: value(CGROUP_MEMORY_PEAK).unwrap_or(0),
+            limit: value(CGROUP_MEMORY_MAX)?,
+            failcnt: events.get("max").copied().unwrap_or(0),
+        };
+        // the swap files only exist when the kernel accounts swap
+        let swap = if cgroup_path.join(CGROUP_MEMORY_SWAP_CURRENT).exists() {
+            MemoryData {
+                usage: value(CGROUP_MEMORY_SWAP_CURRENT)?,
+                limit: value(CGROUP_MEMORY_SWAP_MAX)?,
+                ..Default::default()
+            }
+        } else {
+            MemoryData::default()
+        };
+        Ok(MemoryStats {
+            usage,
+            swap,
+            stats: stats::read_flat_keyed(&cgroup_path.join(CGROUP_MEMORY_STAT))?,
+        })
+    }
+
+    // Watch memory.events, which the kernel modifies when it kills a process of the cgroup.
+    pub fn oom_notifier(cgroup_path: &Path) -> Result<OomNotifier> {
+        let path = cgroup_path.join(CGROUP_MEMORY_EVENTS);
+        let inotify = Inotify::init(InitFlags::IN_CLOEXEC | InitFlags::IN_NONBLOCK)?;
+        let mut notifier = OomNotifier::MemoryEvents {
+            inotify,
+            path: path.clone(),
+            oom_kills: 0,
+        };
+        inotify.add_watch(&path, AddWatchFlags::IN_MODIFY)?;
+        // only report the OOM kills from now on
+        notifier.read()?;
+        Ok(notifier)
+    }
+}
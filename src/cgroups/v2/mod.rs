@@ -0,0 +1,12 @@
+mod bpf;
+mod controller;
+mod cpu;
+mod cpuset;
+mod devices;
+mod freezer;
+mod hugetlb;
+mod io;
+mod manager;
+mod memory;
+mod pids;
+pub use manager::Manager;
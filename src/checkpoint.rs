@@ -0,0 +1,82 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use clap::Args;
+use nix::fcntl::{self, OFlag};
+use nix::sys::stat::Mode;
+use nix::unistd;
+
+use crate::container::{Container, ContainerStatus};
+use crate::criu::{self, Criu, CriuOpts};
+
+#[derive(Debug, Args)]
+pub struct Checkpoint {
+    // where CRIU writes the images and dump.log
+    #[clap(long)]
+    image_path: PathBuf,
+    // keep the container running once dumped
+    #[clap(long)]
+    leave_running: bool,
+    #[clap(long, default_value = "criu")]
+    criu: PathBuf,
+    pub container_id: String,
+}
+
+impl Checkpoint {
+    pub fn exec(&self, root_path: PathBuf) -> Result<()> {
+        let container_root = root_path.join(&self.container_id);
+        if !container_root.exists() {
+            bail!("{} doesn't exists.", self.container_id)
+        }
+        let container = Container::load(container_root)?.refresh_status()?;
+        if !matches!(
+            container.status(),
+            ContainerStatus::Running | ContainerStatus::Paused
+        ) {
+            bail!(
+                "{} could not be checkpointed because it was {:?}",
+                container.id(),
+                container.status()
+            )
+        }
+        let pid = container.pid().unwrap();
+        let spec = container.spec()?;
+        let rootfs = fs::canonicalize(Path::new(&container.state.bundle).join(&spec.root.path))?;
+
+        fs::create_dir_all(&self.image_path)?;
+        // left open across exec for CRIU
+        let images_dir = fcntl::open(
+            &self.image_path,
+            OFlag::O_DIRECTORY | OFlag::O_RDONLY,
+            Mode::empty(),
+        )?;
+        let opts = CriuOpts {
+            images_dir_fd: images_dir,
+            pid: Some(pid.as_raw()),
+            leave_running: self.leave_running,
+            log_file: "dump.log".to_string(),
+            root: Some(rootfs),
+            ext_mnt: criu::dump_mounts(&spec, pid),
+            manage_cgroups: true,
+            external: criu::external_namespaces(&spec)?,
+            // CRIU can not seize the frozen processes of a paused container one by one
+            freeze_cgroup: if container.status() == ContainerStatus::Paused {
+                Some(container.cgroup_manager()?.freezer_path()?)
+            } else {
+                None
+            },
+            ..Default::default()
+        };
+        let result = Criu::start(&self.criu)
+            .and_then(|mut criu| criu.request(criu::REQ_DUMP, &opts, |_, _| Ok(())));
+        unistd::close(images_dir)?;
+        result?;
+        log::debug!("Checkpointed {} into {:?}", container.id(), self.image_path);
+
+        if !self.leave_running {
+            container.update_status(ContainerStatus::Stopped)?.save()?;
+        }
+        Ok(())
+    }
+}
@@ -1,11 +1,19 @@
-use std::fs;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use nix::unistd::Pid;
 use procfs::process::{ProcState, Process};
 
+use crate::cgroups::{self, CgroupManager};
 use crate::container::{ContainerStatus, State};
+use crate::spec::{LinuxResources, Spec};
+
+const RESOURCES_FILE: &str = "resources.json";
+const CGROUP_PATH_FILE: &str = "cgroup_path";
 
 #[derive(Debug, Clone)]
 pub struct Container {
@@ -21,8 +29,8 @@ impl Container {
         status: ContainerStatus,
         pid: Option<i32>,
         bundle: &str,
-        container_root: &PathBuf,
-    ) -> Result<(Self)> {
+        container_root: &Path,
+    ) -> Result<Self> {
         let container_root = fs::canonicalize(container_root)?;
         let state = State::new(container_id, status, pid, bundle);
         Ok(Self {
@@ -46,7 +54,9 @@ impl Container {
                     match proc.stat.state().unwrap() {
                         ProcState::Zombie | ProcState::Dead => ContainerStatus::Stopped,
                         _ => match self.status() {
-                            ContainerStatus::Creating | ContainerStatus::Created => self.status(),
+                            ContainerStatus::Creating
+                            | ContainerStatus::Created
+                            | ContainerStatus::Paused => self.status(),
                             _ => ContainerStatus::Running,
                         },
                     }
@@ -65,24 +75,21 @@ impl Container {
     }
 
     pub fn set_pid(&self, pid: i32) -> Self {
-        Self::new(
-            self.state.id.as_str(),
-            self.state.status,
-            Some(pid),
-            self.state.bundle.as_str(),
-            &self.root,
-        )
-        .expect("unexpected error")
+        let mut new_container = self.clone();
+        new_container.state.pid = Some(pid);
+        new_container
+    }
+
+    pub fn set_annotations(&self, annotations: HashMap<String, String>) -> Self {
+        let mut new_container = self.clone();
+        new_container.state.annotations = annotations;
+        new_container
     }
 
     pub fn update_status(&self, status: ContainerStatus) -> Result<Self> {
-        Self::new(
-            self.state.id.as_str(),
-            status,
-            self.state.pid,
-            self.state.bundle.as_str(),
-            &self.root,
-        )
+        let mut new_container = self.clone();
+        new_container.state.status = status;
+        Ok(new_container)
     }
 
     pub fn can_start(&self) -> bool {
@@ -93,6 +100,85 @@ impl Container {
         self.state.pid.map(Pid::from_raw)
     }
 
+    pub fn spec(&self) -> Result<Spec> {
+        Spec::load(Path::new(&self.state.bundle).join("config.json"))
+    }
+
+    // The resources of the cgroup, which update may have changed since the creation.
+    pub fn resources(&self) -> Result<LinuxResources> {
+        let path = self.root.join(RESOURCES_FILE);
+        if path.exists() {
+            return Ok(serde_json::from_reader(File::open(path)?)?);
+        }
+        Ok(self
+            .spec()?
+            .linux
+            .and_then(|linux| linux.resources)
+            .unwrap_or_default())
+    }
+
+    pub fn save_resources(&self, resources: &LinuxResources) -> Result<()> {
+        let file = File::create(self.root.join(RESOURCES_FILE))?;
+        serde_json::to_writer(file, resources)?;
+        Ok(())
+    }
+
+    // The cgroup resolved at the creation, which does not depend on the bundle staying in place.
+    pub fn cgroup_path(&self) -> Result<PathBuf> {
+        let path = self.root.join(CGROUP_PATH_FILE);
+        if path.exists() {
+            return Ok(OsString::from_vec(fs::read(path)?).into());
+        }
+        let cgroups_path = self.spec()?.linux.and_then(|linux| linux.cgroups_path);
+        Ok(cgroups::get_cgroup_path(&cgroups_path, self.id()))
+    }
+
+    pub fn save_cgroup_path(&self, cgroup_path: &Path) -> Result<()> {
+        fs::write(
+            self.root.join(CGROUP_PATH_FILE),
+            cgroup_path.as_os_str().as_bytes(),
+        )?;
+        Ok(())
+    }
+
+    pub fn cgroup_manager(&self) -> Result<Box<dyn CgroupManager>> {
+        cgroups::create_cgroup_manager(self.cgroup_path()?)
+    }
+
+    // Collect the pids of every process in the container's cgroup. Without a cgroup, fall back to
+    // the processes that share the pid namespace of the container's init process; a container
+    // without its own pid namespace only owns its init process then.
+    pub fn processes(&self) -> Result<Vec<Pid>> {
+        match self
+            .cgroup_manager()
+            .and_then(|cmanager| cmanager.get_all_pids())
+        {
+            Ok(pids) => return Ok(pids),
+            Err(e) => log::debug!("could not read the pids of the cgroup: {}", e),
+        }
+
+        let init_pid = match self.pid() {
+            Some(pid) => pid,
+            None => return Ok(Vec::new()),
+        };
+        let pid_ns = |pid: i32| fs::read_link(format!("/proc/{}/ns/pid", pid));
+        let init_ns = pid_ns(init_pid.as_raw())?;
+        if init_ns == fs::read_link("/proc/self/ns/pid")? {
+            return Ok(vec![init_pid]);
+        }
+
+        let mut pids = Vec::new();
+        for proc in procfs::process::all_processes()? {
+            // processes may exit while we are walking /proc
+            if let Ok(ns) = pid_ns(proc.pid) {
+                if ns == init_ns {
+                    pids.push(Pid::from_raw(proc.pid));
+                }
+            }
+        }
+        Ok(pids)
+    }
+
     pub fn load(container_root: PathBuf) -> Result<Self> {
         let state = State::load(&container_root)?;
         Ok(Self {
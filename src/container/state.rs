@@ -3,6 +3,8 @@ use std::fs;
 use std::{fs::File, path::PathBuf};
 
 use anyhow::{Ok, Result};
+use chrono::{DateTime, Utc};
+use nix::unistd;
 use serde::{Deserialize, Serialize};
 
 const STATE_FILE_PATH: &str = "state.json";
@@ -23,6 +25,12 @@ pub struct State {
     pub bundle: String,
     // Annotations are key values associated with the container.
     pub annotations: HashMap<String, String>,
+    // Created is the time when the container was created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<DateTime<Utc>>,
+    // Creator is the uid of the user who created the container.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub creator: Option<u32>,
 }
 
 impl State {
@@ -33,12 +41,14 @@ impl State {
         bundle: &str,
     ) -> Self {
         Self {
-            oci_version: "v1.0.2".to_string(),
+            oci_version: "1.0.2".to_string(),
             id: container_id.to_string(),
             status,
             pid,
             bundle: bundle.to_string(),
             annotations: HashMap::default(),
+            created: Some(Utc::now()),
+            creator: Some(unistd::geteuid().as_raw()),
         }
     }
 
@@ -84,4 +94,19 @@ impl ContainerStatus {
     pub fn can_start(&self) -> bool {
         matches!(self, ContainerStatus::Created)
     }
+
+    pub fn can_kill(&self) -> bool {
+        matches!(
+            self,
+            ContainerStatus::Created | ContainerStatus::Running | ContainerStatus::Paused
+        )
+    }
+
+    pub fn can_pause(&self) -> bool {
+        matches!(self, ContainerStatus::Running)
+    }
+
+    pub fn can_resume(&self) -> bool {
+        matches!(self, ContainerStatus::Paused)
+    }
 }
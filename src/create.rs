@@ -1,4 +1,8 @@
 use std::fs;
+use std::io::{Read, Write};
+use std::net::Shutdown;
+use std::os::unix::io::RawFd;
+use std::os::unix::net::UnixStream;
 use std::path::{Path, PathBuf};
 use std::process;
 
@@ -6,17 +10,24 @@ use anyhow::{bail, Ok, Result};
 use clap::Args;
 use nix::fcntl;
 use nix::sched;
+use nix::sys::signal::{self, Signal};
 use nix::sys::stat;
 use nix::unistd;
 use nix::unistd::{sethostname, Gid, Uid};
 
-use crate::container::{Container, ContainerStatus};
+use crate::capabilities;
+use crate::cgroups;
+use crate::container::{Container, ContainerStatus, State};
+use crate::hooks;
 use crate::notify_socket::NotifyListener;
 use crate::process::Process;
 use crate::process::{fork::fork_first, fork::fork_init};
 use crate::rootfs;
+use crate::rootless;
+use crate::seccomp;
 use crate::spec;
 use crate::stdio::FileDescriptor;
+use crate::sysctl;
 use crate::tty;
 use crate::utils;
 
@@ -39,7 +50,8 @@ impl Create {
         } else {
             bail!("{} already exists", self.container_id)
         }
-        unistd::chdir(&self.bundle)?;
+        let bundle = fs::canonicalize(&self.bundle)?;
+        unistd::chdir(&bundle)?;
         let spec = spec::Spec::load("config.json")?;
 
         let container_dir = fs::canonicalize(container_dir)?;
@@ -50,9 +62,10 @@ impl Create {
             &self.container_id,
             ContainerStatus::Creating,
             None,
-            self.bundle.to_str().unwrap(),
+            bundle.to_str().unwrap(),
             &container_dir,
-        )?;
+        )?
+        .set_annotations(spec.annotations.clone());
         container.save()?;
 
         let mut notify_socket: NotifyListener = NotifyListener::new(&container_dir)?;
@@ -107,15 +120,47 @@ fn run_container<P: AsRef<Path>>(
         }
     }
 
+    let namespaces = to_enter
+        .iter()
+        .fold(cf, |namespaces, &(space, _)| namespaces | space);
+    sysctl::validate(&linux.sysctl, namespaces)?;
+    if rootless::is_rootless() {
+        rootless::validate(linux)?;
+    }
+
+    let cgroup_path = cgroups::get_cgroup_path(&linux.cgroups_path, container.id());
+    container.save_cgroup_path(&cgroup_path)?;
+    let cmanager = cgroups::create_cgroup_manager(cgroup_path)?;
+    let listener_sockets = seccomp::listener_sockets(linux.seccomp.as_ref())?;
+    let (runtime_sync, init_sync) = UnixStream::pair()?;
+
     match fork_first(
         pid_file,
         cf.contains(sched::CloneFlags::CLONE_NEWUSER),
         linux,
         &container,
+        cmanager.as_ref(),
     )? {
-        Process::Parent(parent) => Ok(Process::Parent(parent)),
+        Process::Parent(parent) => {
+            drop(init_sync);
+            // the saved state has the pid of the init process
+            let container = Container::load(container.root.clone())?;
+            if let Err(e) = finish_creation(spec, &container, runtime_sync, listener_sockets) {
+                if let Some(pid) = container.pid() {
+                    let _ = signal::kill(pid, Signal::SIGKILL);
+                }
+                return Err(e);
+            }
+            container.update_status(ContainerStatus::Created)?.save()?;
+            log::debug!(
+                "Save container status: {:?} in {:?}",
+                container,
+                container.root
+            );
+            Ok(Process::Parent(parent))
+        }
         Process::Child(child) => {
-            setid(Uid::from_raw(0), Gid::from_raw(0))?;
+            utils::setid(Uid::from_raw(0), Gid::from_raw(0))?;
             if let Some(csocketfd) = csocketfd {
                 tty::ready(csocketfd)?;
             }
@@ -126,7 +171,7 @@ fn run_container<P: AsRef<Path>>(
                 log::debug!("setns()->space:{:?},fd:{:?}", space, fd);
                 unistd::close(fd)?;
                 if space == sched::CloneFlags::CLONE_NEWUSER {
-                    setid(Uid::from_raw(0), Gid::from_raw(0))?;
+                    utils::setid(Uid::from_raw(0), Gid::from_raw(0))?;
                     log::debug!("Set ID");
                 }
             }
@@ -137,41 +182,76 @@ fn run_container<P: AsRef<Path>>(
             match fork_init(child)? {
                 Process::Child(child) => Ok(Process::Child(child)),
                 Process::Init(mut init) => {
+                    drop(runtime_sync);
+                    sysctl::write(&linux.sysctl)?;
                     log::debug!("Execute Rootfs");
                     futures::executor::block_on(rootfs::prepare_rootfs(
                         spec,
                         rootfs,
                         cf.contains(sched::CloneFlags::CLONE_NEWUSER),
                     ))?;
-                    rootfs::pivot_rootfs(&*rootfs)?;
-                    log::debug!("Complete Pivot Root");
 
                     init.ready()?;
                     log::debug!("Init Ready");
 
+                    // the runtime sends the state once it has run the hooks of its namespace
+                    let mut state: State = serde_json::from_reader(&init_sync)?;
+                    if let Some(hooks) = &spec.hooks {
+                        hooks::run_hooks(&hooks.create_container, &state)?;
+                    }
+
+                    rootfs::pivot_rootfs(&*rootfs)?;
+                    log::debug!("Complete Pivot Root");
+                    rootfs::protect_paths(linux)?;
+
+                    if cf.contains(sched::CloneFlags::CLONE_NEWUTS) {
+                        if !spec.hostname.is_empty() {
+                            sethostname(&spec.hostname)?;
+                        }
+                        if !spec.domainname.is_empty() {
+                            utils::setdomainname(&spec.domainname)?;
+                        }
+                    }
+                    utils::set_cwd(&spec.process.cwd)?;
+
+                    if spec.process.no_new_privileges {
+                        let _ = prctl::set_no_new_privileges(true);
+                    }
+                    let listener_socket = listener_sockets.map(|(runtime_socket, init_socket)| {
+                        let _ = unistd::close(runtime_socket);
+                        init_socket
+                    });
+                    // Without no_new_privileges, loading a seccomp filter needs CAP_SYS_ADMIN,
+                    // so it has to happen before the capabilities are dropped. Either way it is
+                    // loaded before the runtime returns, to hand it the notify listener.
+                    if !spec.process.no_new_privileges {
+                        if let Some(seccomp) = &linux.seccomp {
+                            seccomp::initialize_seccomp(seccomp, listener_socket)?;
+                        }
+                    }
+                    for rlimit in &spec.process.rlimits {
+                        utils::set_rlimit(rlimit)?;
+                    }
+                    utils::set_user(&spec.process.user)?;
+                    capabilities::reset_effective()?;
+                    if let Some(caps) = &spec.process.capabilities {
+                        capabilities::drop_privileges(caps)?;
+                    }
+                    if spec.process.no_new_privileges {
+                        if let Some(seccomp) = &linux.seccomp {
+                            seccomp::initialize_seccomp(seccomp, listener_socket)?;
+                        }
+                    }
+
+                    // the container is created
+                    (&init_sync).write_all(&[0])?;
+
                     notify_socket.wait_for_container_start()?;
-                    // if spec.process.no_new_privileges {
-                    //     let _ = prctl::set_no_new_privileges(true);
-                    // }
-
-                    // // set hostname and environment value
-                    // sethostname(&spec.hostname)?;
-                    // utils::set_env_val(&spec.process.env);
-
-                    // setid(
-                    //     Uid::from_raw(spec.process.user.uid),
-                    //     Gid::from_raw(spec.process.user.gid),
-                    // )?;
-                    // capabilities::reset_effective()?;
-                    // if let Some(caps) = &spec.process.capabilities {
-                    //     capabilities::set_capabilities(&caps)?;
-                    // }
-                    // // set rlimits
-                    // for rlimit in &spec.process.rlimits {
-                    //     utils::set_rlimits(rlimit)?;
-                    // }
-
-                    utils::do_exec(&spec.process.args[0], &spec.process.args)?;
+                    if let Some(hooks) = &spec.hooks {
+                        state.status = ContainerStatus::Created;
+                        hooks::run_hooks(&hooks.start_container, &state)?;
+                    }
+                    utils::do_exec(&spec.process.args[0], &spec.process.args, &spec.process.env)?;
                     container.update_status(ContainerStatus::Stopped)?.save()?;
                     log::debug!("update");
 
@@ -184,15 +264,32 @@ fn run_container<P: AsRef<Path>>(
     }
 }
 
-fn setid(uid: Uid, gid: Gid) -> Result<()> {
-    if let Err(e) = prctl::set_keep_capabilities(true) {
-        bail!("set keep capabilities returned {}", e);
-    };
-    unistd::setresgid(gid, gid, gid)?;
-    unistd::setresuid(uid, uid, uid)?;
+// The init process waits for the runtime to run the hooks of the runtime namespace once the
+// rootfs is prepared, then the runtime waits for the init process to be ready to start.
+fn finish_creation(
+    spec: &spec::Spec,
+    container: &Container,
+    mut init_sync: UnixStream,
+    listener_sockets: Option<(RawFd, RawFd)>,
+) -> Result<()> {
+    if let Some(hooks) = &spec.hooks {
+        hooks::run_hooks(&hooks.prestart, &container.state)?;
+        hooks::run_hooks(&hooks.create_runtime, &container.state)?;
+    }
+    // the hooks of the container namespace get the state from the init process
+    serde_json::to_writer(&init_sync, &container.state)?;
+    init_sync.shutdown(Shutdown::Write)?;
 
-    if let Err(e) = prctl::set_keep_capabilities(false) {
-        bail!("set keep capabilities returned {}", e);
-    };
+    let seccomp = spec.linux.as_ref().and_then(|linux| linux.seccomp.as_ref());
+    if let (Some(seccomp), Some((runtime_socket, init_socket))) = (seccomp, listener_sockets) {
+        unistd::close(init_socket)?;
+        let pid = container.pid().unwrap().as_raw();
+        seccomp::forward_listener(seccomp, runtime_socket, &container.state, pid)?;
+    }
+
+    let mut buf = [0];
+    if init_sync.read(&mut buf)? == 0 {
+        bail!("the init process failed to set up the container");
+    }
     Ok(())
 }
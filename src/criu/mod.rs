@@ -0,0 +1,547 @@
+mod proto;
+
+use std::os::unix::io::RawFd;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+
+use anyhow::{bail, Result};
+use nix::fcntl::{self, FcntlArg, FdFlag};
+use nix::sys::socket::{self, AddressFamily, ControlMessageOwned, MsgFlags, SockFlag, SockType};
+use nix::sys::stat;
+use nix::sys::uio::IoVec;
+use nix::unistd::{self, Pid};
+
+use crate::spec::{LinuxNamespaceType, Mount, Spec};
+
+use proto::{Encoder, REQ_NOTIFY};
+pub use proto::{Response, REQ_DUMP, REQ_RESTORE};
+
+// The key under which a joined network namespace is passed to CRIU.
+const EXT_NET_NS_KEY: &str = "extRootNetNS";
+// CRIU logs warnings and errors, as runc does.
+const LOG_LEVEL: i32 = 4;
+// The responses are small, except for the error message.
+const RESPONSE_SIZE: usize = 64 * 1024;
+
+// The options of a dump or a restore, a subset of criu_opts.
+#[derive(Default)]
+pub struct CriuOpts {
+    pub images_dir_fd: RawFd,
+    pub pid: Option<i32>,
+    pub leave_running: bool,
+    // relative to the images directory
+    pub log_file: String,
+    pub notify_scripts: bool,
+    pub root: Option<PathBuf>,
+    // external mounts: the mount point in the container and the source outside
+    pub ext_mnt: Vec<(String, String)>,
+    pub manage_cgroups: bool,
+    pub cg_root: Option<PathBuf>,
+    pub rst_sibling: bool,
+    // the descriptors passed to the restored process under a key
+    pub inherit_fd: Vec<(String, RawFd)>,
+    // external resources such as namespaces
+    pub external: Vec<String>,
+    // the cgroup CRIU freezes the processes with, when they already are
+    pub freeze_cgroup: Option<PathBuf>,
+    pub orphan_pts_master: bool,
+}
+
+impl CriuOpts {
+    fn encode(&self) -> Encoder {
+        let mut opts = Encoder::new();
+        opts.int(1, self.images_dir_fd);
+        if let Some(pid) = self.pid {
+            opts.int(2, pid);
+        }
+        opts.bool(3, self.leave_running)
+            .int(9, LOG_LEVEL)
+            .string(10, &self.log_file)
+            .bool(12, self.notify_scripts);
+        if let Some(root) = &self.root {
+            opts.string(13, &root.to_string_lossy());
+        }
+        for (key, val) in &self.ext_mnt {
+            let mut ext_mnt = Encoder::new();
+            ext_mnt.string(1, key).string(2, val);
+            opts.message(23, ext_mnt);
+        }
+        opts.bool(24, self.manage_cgroups);
+        if let Some(path) = &self.cg_root {
+            let mut cg_root = Encoder::new();
+            cg_root.string(2, &path.to_string_lossy());
+            opts.message(25, cg_root);
+        }
+        opts.bool(26, self.rst_sibling);
+        for (key, fd) in &self.inherit_fd {
+            let mut inherit_fd = Encoder::new();
+            inherit_fd.string(1, key).int(2, *fd);
+            opts.message(27, inherit_fd);
+        }
+        for external in &self.external {
+            opts.string(37, external);
+        }
+        if let Some(path) = &self.freeze_cgroup {
+            opts.string(44, &path.to_string_lossy());
+        }
+        opts.bool(50, self.orphan_pts_master);
+        opts
+    }
+}
+
+// The client of a `criu swrk` process, which serves the requests sent over a socket it inherits.
+pub struct Criu {
+    socket: RawFd,
+    child: Option<Child>,
+}
+
+impl Criu {
+    pub fn start(criu_path: &Path) -> Result<Self> {
+        let (socket, criu_socket) = socket::socketpair(
+            AddressFamily::Unix,
+            SockType::SeqPacket,
+            None,
+            SockFlag::empty(),
+        )?;
+        fcntl::fcntl(socket, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))?;
+        let child = Command::new(criu_path)
+            .arg("swrk")
+            .arg(criu_socket.to_string())
+            .spawn();
+        unistd::close(criu_socket)?;
+        match child {
+            Ok(child) => Ok(Self::connect(socket, Some(child))),
+            Err(e) => {
+                let _ = unistd::close(socket);
+                bail!("failed to run {:?}: {}", criu_path, e)
+            }
+        }
+    }
+
+    // Talk over a SOCK_SEQPACKET socket whose other end is served by CRIU, or by a fake server.
+    fn connect(socket: RawFd, child: Option<Child>) -> Self {
+        Self { socket, child }
+    }
+
+    pub fn pid(&self) -> Option<Pid> {
+        self.child
+            .as_ref()
+            .map(|child| Pid::from_raw(child.id() as i32))
+    }
+
+    // Send a request and wait for its response. CRIU stops at the notifications on the way,
+    // with a file descriptor for some of them, until they are answered.
+    pub fn request<F>(&mut self, typ: u64, opts: &CriuOpts, mut notify: F) -> Result<Response>
+    where
+        F: FnMut(&str, Option<RawFd>) -> Result<()>,
+    {
+        let mut request = Encoder::new();
+        request.uint(1, typ).message(2, opts.encode());
+        self.send(request)?;
+
+        loop {
+            let (response, fd) = self.receive()?;
+            if response.typ == REQ_NOTIFY {
+                let script = response.notify_script.unwrap_or_default();
+                log::debug!("CRIU notification {}", script);
+                let result = notify(&script, fd);
+                if let Some(fd) = fd {
+                    let _ = unistd::close(fd);
+                }
+                let mut answer = Encoder::new();
+                answer.uint(1, REQ_NOTIFY).bool(3, result.is_ok());
+                self.send(answer)?;
+                result?;
+                continue;
+            }
+
+            if response.typ != typ {
+                bail!("unexpected CRIU response of type {}", response.typ)
+            }
+            if !response.success {
+                bail!(
+                    "CRIU failed: {} (errno {}), see {} in the images directory",
+                    response.cr_errmsg.as_deref().unwrap_or("unknown error"),
+                    response.cr_errno.unwrap_or(0),
+                    opts.log_file
+                )
+            }
+            return Ok(response);
+        }
+    }
+
+    fn send(&self, request: Encoder) -> Result<()> {
+        socket::send(self.socket, &request.into_bytes(), MsgFlags::empty())?;
+        Ok(())
+    }
+
+    fn receive(&self) -> Result<(Response, Option<RawFd>)> {
+        let mut buf = vec![0; RESPONSE_SIZE];
+        let (len, fd) = {
+            let iov = [IoVec::from_mut_slice(&mut buf)];
+            let mut cmsg_buffer = nix::cmsg_space!([RawFd; 1]);
+            let msg = socket::recvmsg(
+                self.socket,
+                &iov,
+                Some(&mut cmsg_buffer),
+                MsgFlags::MSG_CMSG_CLOEXEC,
+            )?;
+            let mut fd = None;
+            for cmsg in msg.cmsgs() {
+                if let ControlMessageOwned::ScmRights(fds) = cmsg {
+                    fd = fds.first().copied();
+                }
+            }
+            (msg.bytes, fd)
+        };
+        if len == 0 {
+            bail!("CRIU exited without answering")
+        }
+        Ok((Response::decode(&buf[..len])?, fd))
+    }
+}
+
+impl Drop for Criu {
+    // swrk exits once its socket is closed
+    fn drop(&mut self) {
+        let _ = unistd::close(self.socket);
+        if let Some(child) = &mut self.child {
+            let _ = child.wait();
+        }
+    }
+}
+
+// The bind mounts of the spec and the masked files come from outside the container, so CRIU
+// can't dump them. They are dumped under their mount point as a key.
+pub fn dump_mounts(spec: &Spec, pid: Pid) -> Vec<(String, String)> {
+    let mut mounts: Vec<(String, String)> = bind_mounts(spec)
+        .map(|m| {
+            let destination = m.destination.to_string_lossy().into_owned();
+            (destination.clone(), destination)
+        })
+        .collect();
+    // a masked directory is a tmpfs that CRIU dumps itself
+    let root = Path::new("/proc").join(pid.to_string()).join("root");
+    for path in masked_paths(spec) {
+        if root.join(path.trim_start_matches('/')).is_file() {
+            mounts.push((path.clone(), path.clone()));
+        }
+    }
+    mounts
+}
+
+// The sources to mount again on restore, for the keys of dump_mounts.
+pub fn restore_mounts(spec: &Spec) -> Vec<(String, String)> {
+    let mut mounts: Vec<(String, String)> = bind_mounts(spec)
+        .map(|m| {
+            (
+                m.destination.to_string_lossy().into_owned(),
+                m.source.to_string_lossy().into_owned(),
+            )
+        })
+        .collect();
+    for path in masked_paths(spec) {
+        mounts.push((path.clone(), "/dev/null".to_string()));
+    }
+    mounts
+}
+
+fn bind_mounts(spec: &Spec) -> impl Iterator<Item = &Mount> {
+    spec.mounts
+        .iter()
+        .filter(|m| m.typ == "bind" || m.options.iter().any(|o| o == "bind" || o == "rbind"))
+}
+
+fn masked_paths(spec: &Spec) -> impl Iterator<Item = &String> {
+    spec.linux
+        .iter()
+        .flat_map(|linux| linux.masked_paths.iter())
+}
+
+// A network namespace that the container joined is not dumped, it is given back on restore.
+pub fn external_namespaces(spec: &Spec) -> Result<Vec<String>> {
+    let mut external = Vec::new();
+    if let Some(ns) = joined_network_namespace(spec) {
+        let inode = stat::stat(ns.as_str())?.st_ino;
+        external.push(format!("net[{}]:{}", inode, EXT_NET_NS_KEY));
+    }
+    Ok(external)
+}
+
+// Open the namespaces to hand to the restored process. The descriptors are inherited by CRIU.
+pub fn inherited_namespaces(spec: &Spec) -> Result<Vec<(String, RawFd)>> {
+    let mut inherit_fd = Vec::new();
+    if let Some(ns) = joined_network_namespace(spec) {
+        let fd = fcntl::open(ns.as_str(), fcntl::OFlag::O_RDONLY, stat::Mode::empty())?;
+        inherit_fd.push((EXT_NET_NS_KEY.to_string(), fd));
+    }
+    Ok(inherit_fd)
+}
+
+fn joined_network_namespace(spec: &Spec) -> Option<&String> {
+    spec.linux
+        .as_ref()?
+        .namespaces
+        .iter()
+        .find(|ns| matches!(ns.typ, LinuxNamespaceType::Network) && !ns.path.is_empty())
+        .map(|ns| &ns.path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::thread::{self, JoinHandle};
+
+    use nix::sys::socket::ControlMessage;
+    use proto::Value;
+
+    // A CRIU client whose requests are served by `serve` in a thread.
+    fn fake_server<F>(serve: F) -> (Criu, JoinHandle<()>)
+    where
+        F: FnOnce(RawFd) + Send + 'static,
+    {
+        let (client, server) = socket::socketpair(
+            AddressFamily::Unix,
+            SockType::SeqPacket,
+            None,
+            SockFlag::SOCK_CLOEXEC,
+        )
+        .unwrap();
+        // the client sees the server go away even when an assertion of the server fails
+        let handle = thread::spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| serve(server)));
+            unistd::close(server).unwrap();
+            if let Err(e) = result {
+                panic::resume_unwind(e);
+            }
+        });
+        (Criu::connect(client, None), handle)
+    }
+
+    fn receive(server: RawFd) -> Vec<u8> {
+        let mut buf = vec![0; RESPONSE_SIZE];
+        let len = socket::recv(server, &mut buf, MsgFlags::empty()).unwrap();
+        buf.truncate(len);
+        buf
+    }
+
+    fn send(server: RawFd, message: Encoder, fds: &[RawFd]) {
+        let buf = message.into_bytes();
+        let iov = [IoVec::from_slice(&buf)];
+        let cmsgs = [ControlMessage::ScmRights(fds)];
+        let cmsgs: &[ControlMessage] = if fds.is_empty() { &[] } else { &cmsgs };
+        socket::sendmsg(server, &iov, cmsgs, MsgFlags::empty(), None).unwrap();
+    }
+
+    fn success(typ: u64) -> Encoder {
+        let mut response = Encoder::new();
+        response.uint(1, typ).bool(2, true);
+        response
+    }
+
+    fn notify(script: &str) -> Encoder {
+        let mut notify = Encoder::new();
+        notify.string(1, script);
+        let mut response = Encoder::new();
+        response
+            .uint(1, REQ_NOTIFY)
+            .bool(2, true)
+            .message(5, notify);
+        response
+    }
+
+    fn bytes<'a>(fields: &[(u32, Value<'a>)], field: u32) -> Vec<&'a [u8]> {
+        fields
+            .iter()
+            .filter_map(|(f, value)| match value {
+                Value::Bytes(bytes) if *f == field => Some(*bytes),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn varint(fields: &[(u32, Value)], field: u32) -> Option<u64> {
+        fields.iter().find_map(|(f, value)| match value {
+            Value::Varint(value) if *f == field => Some(*value),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn request_encoding() {
+        let (mut criu, server) = fake_server(|server| {
+            let request = receive(server);
+            let request = proto::decode(&request).unwrap();
+            assert_eq!(varint(&request, 1), Some(REQ_DUMP));
+            let opts = proto::decode(bytes(&request, 2)[0]).unwrap();
+
+            assert_eq!(varint(&opts, 1), Some(7));
+            assert_eq!(varint(&opts, 2), Some(1234));
+            assert_eq!(varint(&opts, 3), Some(1));
+            assert_eq!(varint(&opts, 9), Some(LOG_LEVEL as u64));
+            assert_eq!(bytes(&opts, 10), [b"dump.log"]);
+            assert_eq!(bytes(&opts, 13), [b"/rootfs"]);
+            assert_eq!(varint(&opts, 24), Some(1));
+            assert_eq!(varint(&opts, 50), Some(0));
+            assert_eq!(bytes(&opts, 37), [b"net[1]:extRootNetNS"]);
+            assert_eq!(bytes(&opts, 44), [b"/sys/fs/cgroup/freezer/test"]);
+
+            let ext_mnt: Vec<_> = bytes(&opts, 23)
+                .into_iter()
+                .map(|ext_mnt| {
+                    let ext_mnt = proto::decode(ext_mnt).unwrap();
+                    (
+                        bytes(&ext_mnt, 1)[0].to_vec(),
+                        bytes(&ext_mnt, 2)[0].to_vec(),
+                    )
+                })
+                .collect();
+            assert_eq!(
+                ext_mnt,
+                [
+                    (b"/data".to_vec(), b"/srv/data".to_vec()),
+                    (b"/proc/kcore".to_vec(), b"/dev/null".to_vec())
+                ]
+            );
+            let cg_root = proto::decode(bytes(&opts, 25)[0]).unwrap();
+            assert_eq!(bytes(&cg_root, 2), [b"/rust-runtime/test"]);
+            let inherit_fd = proto::decode(bytes(&opts, 27)[0]).unwrap();
+            assert_eq!(bytes(&inherit_fd, 1), [b"extRootNetNS"]);
+            assert_eq!(varint(&inherit_fd, 2), Some(9));
+
+            send(server, success(REQ_DUMP), &[]);
+        });
+
+        let opts = CriuOpts {
+            images_dir_fd: 7,
+            pid: Some(1234),
+            leave_running: true,
+            log_file: "dump.log".to_string(),
+            root: Some(PathBuf::from("/rootfs")),
+            ext_mnt: vec![
+                ("/data".to_string(), "/srv/data".to_string()),
+                ("/proc/kcore".to_string(), "/dev/null".to_string()),
+            ],
+            manage_cgroups: true,
+            cg_root: Some(PathBuf::from("/rust-runtime/test")),
+            inherit_fd: vec![("extRootNetNS".to_string(), 9)],
+            external: vec!["net[1]:extRootNetNS".to_string()],
+            freeze_cgroup: Some(PathBuf::from("/sys/fs/cgroup/freezer/test")),
+            ..Default::default()
+        };
+        let response = criu.request(REQ_DUMP, &opts, |_, _| Ok(())).unwrap();
+        assert!(response.success);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn notifications_are_answered() {
+        let (mut criu, server) = fake_server(|server| {
+            receive(server);
+            send(server, notify("setup-namespaces"), &[]);
+            let answer = receive(server);
+            let answer = proto::decode(&answer).unwrap();
+            assert_eq!(varint(&answer, 1), Some(REQ_NOTIFY));
+            assert_eq!(varint(&answer, 3), Some(1));
+
+            send(server, notify("post-restore"), &[]);
+            let answer = receive(server);
+            assert_eq!(varint(&proto::decode(&answer).unwrap(), 3), Some(0));
+        });
+
+        let mut scripts = Vec::new();
+        let result = criu.request(REQ_RESTORE, &CriuOpts::default(), |script, fd| {
+            assert!(fd.is_none());
+            scripts.push(script.to_string());
+            if script == "post-restore" {
+                bail!("post-restore failed")
+            }
+            Ok(())
+        });
+        assert_eq!(result.unwrap_err().to_string(), "post-restore failed");
+        assert_eq!(scripts, ["setup-namespaces", "post-restore"]);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn orphan_pts_master_comes_with_a_descriptor() {
+        let (mut criu, server) = fake_server(|server| {
+            receive(server);
+            let (read, write) = unistd::pipe().unwrap();
+            unistd::write(write, b"pts").unwrap();
+            send(server, notify("orphan-pts-master"), &[read]);
+            unistd::close(read).unwrap();
+            unistd::close(write).unwrap();
+            receive(server);
+
+            let mut restore = Encoder::new();
+            restore.int(1, 42);
+            let mut response = success(REQ_RESTORE);
+            response.message(4, restore);
+            send(server, response, &[]);
+        });
+
+        let mut received = Vec::new();
+        let response = criu
+            .request(REQ_RESTORE, &CriuOpts::default(), |script, fd| {
+                let mut buf = [0; 3];
+                unistd::read(fd.unwrap(), &mut buf)?;
+                received.push((script.to_string(), buf));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(received, [("orphan-pts-master".to_string(), *b"pts")]);
+        assert_eq!(response.restored_pid, Some(42));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn failures_carry_the_error_of_criu() {
+        let (mut criu, server) = fake_server(|server| {
+            receive(server);
+            let mut response = Encoder::new();
+            response
+                .uint(1, REQ_DUMP)
+                .bool(2, false)
+                .int(7, libc::EPERM)
+                .string(9, "can't dump the task");
+            send(server, response, &[]);
+        });
+
+        let opts = CriuOpts {
+            log_file: "dump.log".to_string(),
+            ..Default::default()
+        };
+        let err = criu.request(REQ_DUMP, &opts, |_, _| Ok(())).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "CRIU failed: can't dump the task (errno 1), see dump.log in the images directory"
+        );
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn criu_exits_without_answering() {
+        let (mut criu, server) = fake_server(|server| {
+            receive(server);
+        });
+        let err = criu
+            .request(REQ_DUMP, &CriuOpts::default(), |_, _| Ok(()))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "CRIU exited without answering");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn response_of_another_request() {
+        let (mut criu, server) = fake_server(|server| {
+            receive(server);
+            send(server, success(REQ_DUMP), &[]);
+        });
+        assert!(criu
+            .request(REQ_RESTORE, &CriuOpts::default(), |_, _| Ok(()))
+            .is_err());
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,273 @@
+// Just enough of the protobuf wire format to speak the RPC protocol of CRIU, whose messages are
+// described in images/rpc.proto of its sources.
+
+use anyhow::{bail, Result};
+
+const WIRE_VARINT: u8 = 0;
+const WIRE_FIXED64: u8 = 1;
+const WIRE_BYTES: u8 = 2;
+const WIRE_FIXED32: u8 = 5;
+
+// criu_req_type
+pub const REQ_DUMP: u64 = 1;
+pub const REQ_RESTORE: u64 = 2;
+pub const REQ_NOTIFY: u64 = 6;
+
+#[derive(Default)]
+pub struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buf.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.buf.push(value as u8);
+    }
+
+    fn key(&mut self, field: u32, wire_type: u8) {
+        self.varint(((field as u64) << 3) | wire_type as u64);
+    }
+
+    pub fn uint(&mut self, field: u32, value: u64) -> &mut Self {
+        self.key(field, WIRE_VARINT);
+        self.varint(value);
+        self
+    }
+
+    // negative int32 values are sign extended to ten bytes
+    pub fn int(&mut self, field: u32, value: i32) -> &mut Self {
+        self.uint(field, value as i64 as u64)
+    }
+
+    pub fn bool(&mut self, field: u32, value: bool) -> &mut Self {
+        self.uint(field, value as u64)
+    }
+
+    pub fn bytes(&mut self, field: u32, value: &[u8]) -> &mut Self {
+        self.key(field, WIRE_BYTES);
+        self.varint(value.len() as u64);
+        self.buf.extend_from_slice(value);
+        self
+    }
+
+    pub fn string(&mut self, field: u32, value: &str) -> &mut Self {
+        self.bytes(field, value.as_bytes())
+    }
+
+    pub fn message(&mut self, field: u32, message: Encoder) -> &mut Self {
+        self.bytes(field, &message.buf)
+    }
+}
+
+pub enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+}
+
+// Split a message into its fields, skipping the fixed size ones which CRIU's responses don't
+// use.
+pub fn decode(mut buf: &[u8]) -> Result<Vec<(u32, Value<'_>)>> {
+    let mut fields = Vec::new();
+    while !buf.is_empty() {
+        let key = read_varint(&mut buf)?;
+        let field = (key >> 3) as u32;
+        match (key & 7) as u8 {
+            WIRE_VARINT => fields.push((field, Value::Varint(read_varint(&mut buf)?))),
+            WIRE_BYTES => {
+                let len = read_varint(&mut buf)? as usize;
+                if len > buf.len() {
+                    bail!("truncated protobuf message")
+                }
+                fields.push((field, Value::Bytes(&buf[..len])));
+                buf = &buf[len..];
+            }
+            WIRE_FIXED64 => buf = skip(buf, 8)?,
+            WIRE_FIXED32 => buf = skip(buf, 4)?,
+            wire_type => bail!("unsupported protobuf wire type {}", wire_type),
+        }
+    }
+    Ok(fields)
+}
+
+fn read_varint(buf: &mut &[u8]) -> Result<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = match buf.split_first() {
+            Some(split) => split,
+            None => bail!("truncated protobuf message"),
+        };
+        *buf = rest;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    bail!("protobuf varint is too long")
+}
+
+fn skip(buf: &[u8], len: usize) -> Result<&[u8]> {
+    if len > buf.len() {
+        bail!("truncated protobuf message")
+    }
+    Ok(&buf[len..])
+}
+
+// criu_resp, without the fields the runtime has no use for
+#[derive(Debug, Default)]
+pub struct Response {
+    pub typ: u64,
+    pub success: bool,
+    // restore.pid
+    pub restored_pid: Option<i32>,
+    // notify.script
+    pub notify_script: Option<String>,
+    pub cr_errno: Option<i32>,
+    pub cr_errmsg: Option<String>,
+}
+
+impl Response {
+    pub fn decode(buf: &[u8]) -> Result<Self> {
+        let mut response = Response::default();
+        for (field, value) in decode(buf)? {
+            match (field, value) {
+                (1, Value::Varint(typ)) => response.typ = typ,
+                (2, Value::Varint(success)) => response.success = success != 0,
+                (4, Value::Bytes(restore)) => {
+                    for (field, value) in decode(restore)? {
+                        if let (1, Value::Varint(pid)) = (field, value) {
+                            response.restored_pid = Some(pid as i32);
+                        }
+                    }
+                }
+                (5, Value::Bytes(notify)) => {
+                    for (field, value) in decode(notify)? {
+                        if let (1, Value::Bytes(script)) = (field, value) {
+                            response.notify_script =
+                                Some(String::from_utf8_lossy(script).into_owned());
+                        }
+                    }
+                }
+                (7, Value::Varint(errno)) => response.cr_errno = Some(errno as i32),
+                (9, Value::Bytes(errmsg)) => {
+                    response.cr_errmsg = Some(String::from_utf8_lossy(errmsg).into_owned())
+                }
+                _ => {}
+            }
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut nested = Encoder::new();
+        nested.string(1, "key").int(2, 3);
+        let mut message = Encoder::new();
+        message
+            .uint(1, 300)
+            .int(2, -1)
+            .bool(3, true)
+            .bool(4, false)
+            .string(5, "")
+            .message(6, nested)
+            .uint(2000, u64::MAX);
+        let buf = message.into_bytes();
+        let fields = decode(&buf).unwrap();
+
+        assert_eq!(fields.len(), 7);
+        assert!(matches!(fields[0], (1, Value::Varint(300))));
+        assert!(matches!(fields[1], (2, Value::Varint(v)) if v as i32 == -1));
+        assert!(matches!(fields[2], (3, Value::Varint(1))));
+        assert!(matches!(fields[3], (4, Value::Varint(0))));
+        assert!(matches!(fields[4], (5, Value::Bytes(b""))));
+        assert!(matches!(fields[6], (2000, Value::Varint(u64::MAX))));
+        let nested = match fields[5] {
+            (6, Value::Bytes(nested)) => decode(nested).unwrap(),
+            _ => panic!("no nested message"),
+        };
+        assert!(matches!(nested[0], (1, Value::Bytes(b"key"))));
+        assert!(matches!(nested[1], (2, Value::Varint(3))));
+    }
+
+    #[test]
+    fn varint_encoding() {
+        let mut message = Encoder::new();
+        message.uint(1, 150);
+        assert_eq!(message.into_bytes(), [0x08, 0x96, 0x01]);
+
+        // int32 -1 takes ten bytes, as protobuf sign extends it to 64 bits
+        let mut message = Encoder::new();
+        message.int(1, -1);
+        let buf = message.into_bytes();
+        assert_eq!(buf.len(), 11);
+        let mut varint = &buf[1..];
+        assert_eq!(read_varint(&mut varint).unwrap(), u64::MAX);
+        assert!(varint.is_empty());
+    }
+
+    #[test]
+    fn truncated_input() {
+        assert!(read_varint(&mut &[][..]).is_err());
+        assert!(read_varint(&mut &[0x96][..]).is_err());
+        assert!(read_varint(&mut &[0x80; 11][..]).is_err());
+        // a key without its value
+        assert!(decode(&[0x08]).is_err());
+        // a string longer than the message
+        assert!(decode(&[0x0a, 0x05, b'a']).is_err());
+        assert!(decode(&[0x0d, 0x00]).is_err());
+        assert!(decode(&[0x0b]).is_err());
+    }
+
+    #[test]
+    fn fixed_size_fields_are_skipped() {
+        let buf = [0x0d, 1, 2, 3, 4, 0x11, 1, 2, 3, 4, 5, 6, 7, 8, 0x18, 0x01];
+        let fields = decode(&buf).unwrap();
+        assert_eq!(fields.len(), 1);
+        assert!(matches!(fields[0], (3, Value::Varint(1))));
+    }
+
+    #[test]
+    fn response() {
+        let mut restore = Encoder::new();
+        restore.int(1, 4321);
+        let mut notify = Encoder::new();
+        notify.string(1, "post-restore").int(2, 4321);
+        let mut response = Encoder::new();
+        response
+            .uint(1, REQ_RESTORE)
+            .bool(2, true)
+            .message(4, restore)
+            .message(5, notify)
+            .int(7, -2)
+            .string(9, "message")
+            .uint(99, 1);
+        let response = Response::decode(&response.into_bytes()).unwrap();
+
+        assert_eq!(response.typ, REQ_RESTORE);
+        assert!(response.success);
+        assert_eq!(response.restored_pid, Some(4321));
+        assert_eq!(response.notify_script.as_deref(), Some("post-restore"));
+        assert_eq!(response.cr_errno, Some(-2));
+        assert_eq!(response.cr_errmsg.as_deref(), Some("message"));
+
+        let response = Response::decode(&[]).unwrap();
+        assert_eq!(response.typ, 0);
+        assert!(!response.success);
+        assert_eq!(response.restored_pid, None);
+    }
+}
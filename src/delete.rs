@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use clap::Args;
+use nix::sys::signal::{self, Signal};
+
+use crate::cgroups::FreezerState;
+use crate::container::{Container, ContainerStatus};
+use crate::hooks;
+
+const KILL_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Args)]
+pub struct Delete {
+    #[clap(short, long)]
+    pub force: bool,
+    pub container_id: String,
+}
+
+impl Delete {
+    pub fn exec(&self, root_path: PathBuf) -> Result<()> {
+        let container_root = root_path.join(&self.container_id);
+        if !container_root.exists() {
+            bail!("{} doesn't exists.", self.container_id)
+        }
+        let container = Container::load(container_root)?.refresh_status()?;
+        if container.status() != ContainerStatus::Stopped {
+            if !self.force {
+                let err_msg = format!(
+                    "{} could not be deleted because it was {:?}",
+                    container.id(),
+                    container.status()
+                );
+                log::error!("{}", err_msg);
+                bail!(err_msg);
+            }
+            kill_processes(&container)?;
+        }
+
+        match container.cgroup_manager() {
+            Ok(cmanager) => cmanager.remove()?,
+            Err(e) => log::warn!("could not remove the cgroup of {}: {}", container.id(), e),
+        }
+
+        // the bundle may be gone already
+        let hooks = match container.spec() {
+            Ok(spec) => spec.hooks,
+            Err(e) => {
+                log::warn!("could not load the spec of {}: {}", container.id(), e);
+                None
+            }
+        };
+
+        log::debug!("Delete: {:?}", container.root);
+        fs::remove_dir_all(&container.root)?;
+        if let Some(hooks) = hooks {
+            hooks::run_hooks_or_warn(&hooks.poststop, &container.state);
+        }
+        Ok(())
+    }
+}
+
+// Kill every process left in the container and wait for its init process to go away.
+fn kill_processes(container: &Container) -> Result<()> {
+    for pid in container.processes()? {
+        log::debug!("kill {:?} with {:?}", pid, Signal::SIGKILL);
+        signal::kill(pid, Signal::SIGKILL)?;
+    }
+    // frozen processes only die once thawed
+    if container.status() == ContainerStatus::Paused {
+        container.cgroup_manager()?.freeze(FreezerState::Thawed)?;
+    }
+
+    let deadline = Instant::now() + KILL_TIMEOUT;
+    while container.refresh_status()?.status() != ContainerStatus::Stopped {
+        if Instant::now() > deadline {
+            bail!("{} did not stop after SIGKILL", container.id());
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use clap::Args;
+use mio::unix::SourceFd;
+use mio::{Events as MioEvents, Interest, Poll, Token};
+use serde::Serialize;
+
+use crate::cgroups::stats::Stats;
+use crate::container::{Container, ContainerStatus};
+
+const OOM: Token = Token(0);
+
+#[derive(Debug, Args)]
+pub struct Events {
+    // seconds between two stats
+    #[clap(long, default_value = "5")]
+    interval: u64,
+    // print the stats once and exit
+    #[clap(long)]
+    stats: bool,
+    pub container_id: String,
+}
+
+// One JSON line of the stream.
+#[derive(Serialize)]
+struct Event<'a> {
+    #[serde(rename = "type")]
+    typ: &'a str,
+    id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Stats>,
+}
+
+impl Events {
+    pub fn exec(&self, root_path: PathBuf) -> Result<()> {
+        let container_root = root_path.join(&self.container_id);
+        if !container_root.exists() {
+            bail!("{} doesn't exists.", self.container_id)
+        }
+        let container = Container::load(container_root)?.refresh_status()?;
+        if container.status() == ContainerStatus::Stopped {
+            bail!("{} is stopped", container.id())
+        }
+        let cmanager = container.cgroup_manager()?;
+
+        if self.stats {
+            return print_event(&Event {
+                typ: "stats",
+                id: container.id(),
+                data: Some(cmanager.stats()?),
+            });
+        }
+        if self.interval == 0 {
+            bail!("the interval must be at least one second")
+        }
+
+        let mut poll = Poll::new()?;
+        // without a memory controller, the stream only has the stats
+        let mut oom_notifier = match cmanager.oom_notifier() {
+            Ok(notifier) => {
+                poll.registry()
+                    .register(&mut SourceFd(&notifier.fd()), OOM, Interest::READABLE)?;
+                Some(notifier)
+            }
+            Err(e) => {
+                log::warn!("could not watch the OOM events: {}", e);
+                None
+            }
+        };
+        let mut events = MioEvents::with_capacity(8);
+        let interval = Duration::from_secs(self.interval);
+        let mut next_stats = Instant::now();
+        loop {
+            let now = Instant::now();
+            if now >= next_stats {
+                // the stream ends with the container
+                let status = Container::load(container.root.clone())?
+                    .refresh_status()?
+                    .status();
+                if status == ContainerStatus::Stopped {
+                    return Ok(());
+                }
+                print_event(&Event {
+                    typ: "stats",
+                    id: container.id(),
+                    data: Some(cmanager.stats()?),
+                })?;
+                next_stats = now + interval;
+            }
+
+            let timeout = next_stats.saturating_duration_since(Instant::now());
+            if let Err(e) = poll.poll(&mut events, Some(timeout)) {
+                if e.kind() != io::ErrorKind::Interrupted {
+                    return Err(e.into());
+                }
+            }
+            for event in events.iter() {
+                if let (OOM, Some(oom_notifier)) = (event.token(), oom_notifier.as_mut()) {
+                    for _ in 0..oom_notifier.read()? {
+                        print_event(&Event {
+                            typ: "oom",
+                            id: container.id(),
+                            data: None,
+                        })?;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn print_event(event: &Event) -> Result<()> {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    serde_json::to_writer(&mut stdout, event)?;
+    writeln!(stdout)?;
+    stdout.flush()?;
+    Ok(())
+}
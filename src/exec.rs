@@ -0,0 +1,294 @@
+use std::fs::{self, File};
+use std::io::ErrorKind;
+use std::os::unix::io::RawFd;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use anyhow::{bail, Result};
+use clap::Args;
+use nix::fcntl::{self, OFlag};
+use nix::sched::{self, CloneFlags};
+use nix::sys::stat::Mode;
+use nix::sys::wait::{waitpid, WaitStatus};
+use nix::unistd::{self, ForkResult, Pid};
+use procfs::process::Process;
+
+use crate::capabilities;
+use crate::container::{Container, ContainerStatus};
+use crate::rootless;
+use crate::seccomp;
+use crate::spec;
+use crate::stdio::FileDescriptor;
+use crate::tty;
+use crate::utils;
+
+// The user namespace is joined first so that we keep the privileges needed to join the others.
+const NAMESPACES: [(&str, CloneFlags); 7] = [
+    ("user", CloneFlags::CLONE_NEWUSER),
+    ("ipc", CloneFlags::CLONE_NEWIPC),
+    ("uts", CloneFlags::CLONE_NEWUTS),
+    ("net", CloneFlags::CLONE_NEWNET),
+    ("pid", CloneFlags::CLONE_NEWPID),
+    ("cgroup", CloneFlags::CLONE_NEWCGROUP),
+    ("mnt", CloneFlags::CLONE_NEWNS),
+];
+
+#[derive(Debug, Args)]
+pub struct Exec {
+    #[clap(short, long)]
+    process: Option<PathBuf>,
+    #[clap(short, long)]
+    tty: bool,
+    #[clap(long)]
+    console_socket: Option<String>,
+    #[clap(short, long)]
+    detach: bool,
+    #[clap(long)]
+    pid_file: Option<String>,
+    pub container_id: String,
+    #[clap(trailing_var_arg = true)]
+    command: Vec<String>,
+}
+
+impl Exec {
+    pub fn exec(&self, root_path: PathBuf) -> Result<()> {
+        let container_root = root_path.join(&self.container_id);
+        if !container_root.exists() {
+            bail!("{} doesn't exists.", self.container_id)
+        }
+        let container = Container::load(container_root)?.refresh_status()?;
+        let init_pid = match (container.status(), container.pid()) {
+            (ContainerStatus::Running, Some(pid)) => pid,
+            _ => {
+                let err_msg = format!(
+                    "could not exec in {} because it was {:?}",
+                    container.id(),
+                    container.status()
+                );
+                log::error!("{}", err_msg);
+                bail!(err_msg);
+            }
+        };
+
+        let process = self.load_process(&container)?;
+        // the processes of a container share its seccomp profile
+        let seccomp = container.spec()?.linux.and_then(|linux| linux.seccomp);
+        let listener_sockets = seccomp::listener_sockets(seccomp.as_ref())?;
+        let csocketfd = if process.terminal {
+            match &self.console_socket {
+                Some(console_socket) => Some(tty::connect_console_socket(console_socket)?),
+                None => bail!("--console-socket is required to allocate a tty"),
+            }
+        } else {
+            None
+        };
+
+        let namespaces = open_namespaces(init_pid)?;
+
+        let (rfd, wfd) = unistd::pipe2(OFlag::O_CLOEXEC)?;
+        // the exec process waits for the runtime to move it into the cgroups of the container
+        let (joined_rfd, joined_wfd) = unistd::pipe2(OFlag::O_CLOEXEC)?;
+        match unsafe { unistd::fork()? } {
+            ForkResult::Child => {
+                unistd::close(rfd)?;
+                unistd::close(joined_wfd)?;
+                for (space, fd) in namespaces {
+                    sched::setns(fd, space)?;
+                    log::debug!("setns()->space:{:?},fd:{:?}", space, fd);
+                    unistd::close(fd)?;
+                }
+
+                // joining a pid namespace only takes effect for children
+                match unsafe { unistd::fork()? } {
+                    ForkResult::Child => {
+                        unistd::close(wfd)?;
+                        if unistd::read(joined_rfd, &mut [0])? == 0 {
+                            bail!("the runtime exited before joining the cgroups")
+                        }
+                        unistd::close(joined_rfd)?;
+                        let listener_socket = match listener_sockets {
+                            Some((runtime_socket, process_socket)) => {
+                                unistd::close(runtime_socket)?;
+                                Some(process_socket)
+                            }
+                            None => None,
+                        };
+                        exec_process(&process, seccomp.as_ref(), listener_socket, csocketfd)?;
+                        unreachable!()
+                    }
+                    ForkResult::Parent { child } => {
+                        unistd::close(joined_rfd)?;
+                        unistd::write(wfd, &child.as_raw().to_be_bytes())?;
+                        unistd::close(wfd)?;
+                        if let Some((runtime_socket, process_socket)) = listener_sockets {
+                            unistd::close(runtime_socket)?;
+                            unistd::close(process_socket)?;
+                        }
+                        match waitpid(child, None)? {
+                            WaitStatus::Exited(_, status) => process::exit(status),
+                            WaitStatus::Signaled(_, sig, _) => process::exit(128 + sig as i32),
+                            _ => bail!("abnormal exited!"),
+                        }
+                    }
+                }
+            }
+            ForkResult::Parent { .. } => {
+                unistd::close(wfd)?;
+                unistd::close(joined_rfd)?;
+                let pid = read_pid(rfd)?;
+                log::debug!("exec process pid: {:?}", pid);
+                // only the exec process, the runtime waiting for it stays out of the container
+                join_cgroups(init_pid, pid)?;
+                unistd::write(joined_wfd, &[0])?;
+                unistd::close(joined_wfd)?;
+                if let (Some(seccomp), Some((runtime_socket, process_socket))) =
+                    (&seccomp, listener_sockets)
+                {
+                    unistd::close(process_socket)?;
+                    seccomp::forward_listener(
+                        seccomp,
+                        runtime_socket,
+                        &container.state,
+                        pid.as_raw(),
+                    )?;
+                }
+                if let Some(pid_file) = &self.pid_file {
+                    fs::write(pid_file, format!("{}", pid))?;
+                }
+                if self.detach {
+                    return Ok(());
+                }
+                let exit_code = utils::wait_for_child(pid)?;
+                log::debug!("exec process exited with {}", exit_code);
+                process::exit(exit_code);
+            }
+        }
+    }
+
+    fn load_process(&self, container: &Container) -> Result<spec::Process> {
+        let mut process = if let Some(process) = &self.process {
+            let file = File::open(process)?;
+            serde_json::from_reader::<_, spec::Process>(&file)?
+        } else {
+            if self.command.is_empty() {
+                bail!("either --process or a command has to be given")
+            }
+            let mut process = container.spec()?.process;
+            process.args = self.command.clone();
+            process.terminal = false;
+            process
+        };
+        process.terminal |= self.tty;
+        Ok(process)
+    }
+}
+
+// Open every namespace of the init process that differs from the runtime's.
+fn open_namespaces(init_pid: Pid) -> Result<Vec<(CloneFlags, RawFd)>> {
+    let mut namespaces = Vec::new();
+    for &(name, space) in NAMESPACES.iter() {
+        let ns_path = format!("/proc/{}/ns/{}", init_pid, name);
+        let own_ns = match fs::read_link(format!("/proc/self/ns/{}", name)) {
+            Ok(ns) => ns,
+            // the kernel does not support this namespace
+            Err(_) => continue,
+        };
+        if fs::read_link(&ns_path)? != own_ns {
+            let fd = fcntl::open(&*ns_path, OFlag::O_CLOEXEC, Mode::empty())?;
+            namespaces.push((space, fd));
+        }
+    }
+    Ok(namespaces)
+}
+
+// Move `pid` into the cgroups of the init process, resolving each hierarchy against the cgroup
+// filesystems mounted on the host.
+fn join_cgroups(init_pid: Pid, pid: Pid) -> Result<()> {
+    let mounts = Process::myself()?.mountinfo()?;
+    for cgroup in Process::new(init_pid.as_raw())?.cgroups()? {
+        let mount = mounts.iter().find(|m| {
+            if cgroup.controllers.is_empty() {
+                return m.fs_type == "cgroup2";
+            }
+            m.fs_type == "cgroup"
+                && cgroup
+                    .controllers
+                    .iter()
+                    .all(|c| match c.strip_prefix("name=") {
+                        Some(name) => m.super_options.get("name") == Some(&Some(name.to_string())),
+                        None => m.super_options.contains_key(c),
+                    })
+        });
+        let mount = match mount {
+            Some(mount) => mount,
+            None => continue,
+        };
+        let path = Path::new(&cgroup.pathname);
+        let path = path.strip_prefix(&mount.root).unwrap_or(path);
+        let procs = mount
+            .mount_point
+            .join(path.strip_prefix("/").unwrap_or(path))
+            .join("cgroup.procs");
+        log::debug!("join cgroup {:?}", procs);
+        if let Err(e) = fs::write(&procs, pid.to_string()) {
+            // the cgroups of a rootless container may be the ones of the user
+            if rootless::is_rootless() && e.kind() == ErrorKind::PermissionDenied {
+                log::warn!("could not join {:?}: {}", procs, e);
+                continue;
+            }
+            return Err(e.into());
+        }
+    }
+    Ok(())
+}
+
+fn read_pid(rfd: RawFd) -> Result<Pid> {
+    let mut buf = [0; 4];
+    let mut read = 0;
+    while read < buf.len() {
+        match unistd::read(rfd, &mut buf[read..])? {
+            0 => bail!("the exec process exited before reporting its pid"),
+            n => read += n,
+        }
+    }
+    unistd::close(rfd)?;
+    Ok(Pid::from_raw(i32::from_be_bytes(buf)))
+}
+
+fn exec_process(
+    process: &spec::Process,
+    seccomp: Option<&spec::LinuxSeccomp>,
+    listener_socket: Option<RawFd>,
+    csocketfd: Option<FileDescriptor>,
+) -> Result<()> {
+    if let Some(csocketfd) = csocketfd {
+        tty::ready(csocketfd)?;
+    }
+
+    utils::set_cwd(&process.cwd)?;
+
+    if process.no_new_privileges {
+        let _ = prctl::set_no_new_privileges(true);
+    }
+    // see create::run_container for the reason of the two places where seccomp is loaded
+    if !process.no_new_privileges {
+        if let Some(seccomp) = seccomp {
+            seccomp::initialize_seccomp(seccomp, listener_socket)?;
+        }
+    }
+    for rlimit in &process.rlimits {
+        utils::set_rlimit(rlimit)?;
+    }
+    utils::set_user(&process.user)?;
+    capabilities::reset_effective()?;
+    if let Some(caps) = &process.capabilities {
+        capabilities::drop_privileges(caps)?;
+    }
+    if process.no_new_privileges {
+        if let Some(seccomp) = seccomp {
+            seccomp::initialize_seccomp(seccomp, listener_socket)?;
+        }
+    }
+
+    utils::do_exec(&process.args[0], &process.args, &process.env)
+}
@@ -0,0 +1,74 @@
+use std::io::Write;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+
+use crate::container::State;
+use crate::spec::Hook;
+
+// Run the hooks one after the other, stopping at the first one failing.
+pub fn run_hooks(hooks: &[Hook], state: &State) -> Result<()> {
+    for hook in hooks {
+        run_hook(hook, state)?;
+    }
+    Ok(())
+}
+
+// For the hooks whose failure does not stop the lifecycle of the container.
+pub fn run_hooks_or_warn(hooks: &[Hook], state: &State) {
+    if let Err(e) = run_hooks(hooks, state) {
+        log::warn!("{}", e);
+    }
+}
+
+fn run_hook(hook: &Hook, state: &State) -> Result<()> {
+    log::debug!("run hook {:?}", hook);
+    let mut command = Command::new(&hook.path);
+    // as with execve, args starts with argv[0]
+    if let Some((arg0, args)) = hook.args.split_first() {
+        command.arg0(arg0).args(args);
+    }
+    command.env_clear();
+    for env in &hook.env {
+        match env.split_once('=') {
+            Some((key, value)) => command.env(key, value),
+            None => command.env(env, ""),
+        };
+    }
+
+    let mut child = match command.stdin(Stdio::piped()).spawn() {
+        Ok(child) => child,
+        Err(e) => bail!("failed to run hook {:?}: {}", hook.path, e),
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // the hook does not have to read the state, so writing it must not hold up the timeout
+        let state = serde_json::to_vec(state)?;
+        thread::spawn(move || stdin.write_all(&state));
+    }
+
+    let status = match hook.timeout {
+        Some(timeout) if timeout > 0 => {
+            let deadline = Instant::now() + Duration::from_secs(timeout as u64);
+            loop {
+                if let Some(status) = child.try_wait()? {
+                    break status;
+                }
+                if Instant::now() > deadline {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    bail!("hook {:?} timed out after {}s", hook.path, timeout);
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+        Some(timeout) => bail!("invalid timeout {} of hook {:?}", timeout, hook.path),
+        None => child.wait()?,
+    };
+    if !status.success() {
+        bail!("hook {:?} failed with {}", hook.path, status);
+    }
+    Ok(())
+}
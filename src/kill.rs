@@ -0,0 +1,90 @@
+use std::convert::TryFrom;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use clap::Args;
+use nix::sys::signal::{self, Signal};
+
+use crate::cgroups::FreezerState;
+use crate::container::{Container, ContainerStatus};
+
+#[derive(Debug, Args)]
+pub struct Kill {
+    #[clap(short, long)]
+    all: bool,
+    pub container_id: String,
+    #[clap(default_value = "SIGTERM")]
+    signal: String,
+}
+
+impl Kill {
+    pub fn exec(&self, root_path: PathBuf) -> Result<()> {
+        let container_root = root_path.join(&self.container_id);
+        if !container_root.exists() {
+            bail!("{} doesn't exists.", self.container_id)
+        }
+        let container = Container::load(container_root)?.refresh_status()?;
+        if !container.status().can_kill() {
+            let err_msg = format!(
+                "{} could not be killed because it was {:?}",
+                container.id(),
+                container.status()
+            );
+            log::error!("{}", err_msg);
+            bail!(err_msg);
+        }
+
+        let signal = to_signal(&self.signal)?;
+        let pids = if self.all {
+            container.processes()?
+        } else {
+            container.pid().into_iter().collect()
+        };
+        for pid in pids {
+            log::debug!("kill {:?} with {:?}", pid, signal);
+            signal::kill(pid, signal)?;
+        }
+
+        // The processes of a paused container only handle the signals meant to stop them once
+        // thawed.
+        let container = if container.status() == ContainerStatus::Paused
+            && matches!(signal, Signal::SIGKILL | Signal::SIGTERM)
+        {
+            container.cgroup_manager()?.freeze(FreezerState::Thawed)?;
+            container.update_status(ContainerStatus::Running)?
+        } else {
+            container
+        };
+
+        // SIGKILL can neither be caught nor ignored, so the container is gone even if
+        // the kernel has not reaped its init process yet.
+        let container = if signal == Signal::SIGKILL {
+            container.update_status(ContainerStatus::Stopped)?
+        } else {
+            container.refresh_status()?
+        };
+        container.save()?;
+        Ok(())
+    }
+}
+
+// Accept a signal number (`9`) or a name with or without the `SIG` prefix (`KILL`, `SIGKILL`).
+fn to_signal(signal: &str) -> Result<Signal> {
+    if let Ok(num) = signal.parse::<i32>() {
+        return match Signal::try_from(num) {
+            Ok(sig) => Ok(sig),
+            Err(_) => bail!("{} is not a valid signal number", num),
+        };
+    }
+    let name = signal.to_ascii_uppercase();
+    let name = if name.starts_with("SIG") {
+        name
+    } else {
+        format!("SIG{}", name)
+    };
+    match Signal::from_str(&name) {
+        Ok(sig) => Ok(sig),
+        Err(_) => bail!("{} is not a valid signal name", signal),
+    }
+}
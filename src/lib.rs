@@ -1,12 +1,33 @@
+pub mod capabilities;
+pub mod cgroups;
+pub mod checkpoint;
 pub mod cond;
 pub mod container;
 pub mod create;
+pub mod criu;
+pub mod delete;
+pub mod events;
+pub mod exec;
+pub mod hooks;
+pub mod kill;
+pub mod list;
 pub mod logger;
 pub mod notify_socket;
+pub mod pause;
 pub mod process;
+pub mod ps;
+pub mod restore;
+pub mod resume;
 pub mod rootfs;
+pub mod rootless;
+pub mod run;
+pub mod seccomp;
 pub mod spec;
 pub mod start;
+pub mod state;
 pub mod stdio;
+pub mod sysctl;
 pub mod tty;
+pub mod update;
+pub mod userns;
 pub mod utils;
@@ -0,0 +1,137 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use clap::{Args, ValueEnum};
+use nix::unistd::{Uid, User};
+use serde::Serialize;
+
+use crate::container::{Container, ContainerStatus};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Args)]
+pub struct List {
+    #[clap(short, long, value_enum, default_value = "table")]
+    format: Format,
+    #[clap(short, long)]
+    quiet: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ContainerInfo {
+    id: String,
+    pid: i32,
+    status: String,
+    bundle: String,
+    created: String,
+    owner: String,
+}
+
+impl List {
+    pub fn exec(&self, root_path: PathBuf) -> Result<()> {
+        let mut containers = Vec::new();
+        for entry in fs::read_dir(&root_path)? {
+            let container_root = entry?.path();
+            if !container_root.is_dir() {
+                continue;
+            }
+            let container = match Container::load(container_root.clone()) {
+                Ok(container) => container.refresh_status()?,
+                Err(e) => {
+                    log::warn!("could not load a container in {:?}: {}", container_root, e);
+                    continue;
+                }
+            };
+            containers.push(to_info(&container));
+        }
+        containers.sort_by(|a, b| a.id.cmp(&b.id));
+
+        if self.quiet {
+            for container in &containers {
+                println!("{}", container.id);
+            }
+            return Ok(());
+        }
+        match self.format {
+            Format::Json => println!("{}", serde_json::to_string_pretty(&containers)?),
+            Format::Table => {
+                let rows: Vec<Vec<String>> = containers
+                    .into_iter()
+                    .map(|c| {
+                        vec![
+                            c.id,
+                            c.pid.to_string(),
+                            c.status,
+                            c.bundle,
+                            c.created,
+                            c.owner,
+                        ]
+                    })
+                    .collect();
+                print_table(
+                    &["ID", "PID", "STATUS", "BUNDLE", "CREATED", "OWNER"],
+                    &rows,
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+fn to_info(container: &Container) -> ContainerInfo {
+    let created = container
+        .state
+        .created
+        .map(|created| DateTime::<Local>::from(created).to_rfc3339())
+        .unwrap_or_default();
+    let owner = container
+        .state
+        .creator
+        .map(|uid| match User::from_uid(Uid::from_raw(uid)) {
+            Ok(Some(user)) => user.name,
+            _ => uid.to_string(),
+        })
+        .unwrap_or_default();
+    ContainerInfo {
+        id: container.id().to_string(),
+        // a stopped container has no running process, its pid may belong to another one now
+        pid: match container.status() {
+            ContainerStatus::Stopped => 0,
+            _ => container.pid().map(|pid| pid.as_raw()).unwrap_or(0),
+        },
+        status: format!("{:?}", container.status()).to_lowercase(),
+        bundle: container.state.bundle.clone(),
+        created,
+        owner,
+    }
+}
+
+// Print rows as left-aligned columns separated by two spaces.
+pub fn print_table(header: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = header.iter().map(|h| h.len()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    let format_row = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    println!("{}", format_row(header.to_vec()));
+    for row in rows {
+        println!("{}", format_row(row.iter().map(String::as_str).collect()));
+    }
+}
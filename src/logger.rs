@@ -12,7 +12,7 @@ pub fn init_logger() {
             ))
         })
         .chain(fern::log_file("fern.log").unwrap());
-    let stdout_config = fern::Dispatch::new()
+    let stderr_config = fern::Dispatch::new()
         .level(log::LevelFilter::Debug)
         .format(|out, message, record| {
             out.finish(format_args!(
@@ -23,11 +23,11 @@ pub fn init_logger() {
                 message
             ))
         })
-        .chain(std::io::stdout());
+        .chain(std::io::stderr());
 
     base_config
         .chain(file_config)
-        .chain(stdout_config)
+        .chain(stderr_config)
         .apply()
         .unwrap();
 }
@@ -4,14 +4,29 @@ use clap::Parser;
 use std::fs;
 use std::path::PathBuf;
 
+use rust_runtime::checkpoint;
 use rust_runtime::create;
+use rust_runtime::delete;
+use rust_runtime::events;
+use rust_runtime::exec;
+use rust_runtime::kill;
+use rust_runtime::list;
 use rust_runtime::logger::init_logger;
+use rust_runtime::pause;
+use rust_runtime::ps;
+use rust_runtime::restore;
+use rust_runtime::resume;
+use rust_runtime::rootless;
+use rust_runtime::run;
 use rust_runtime::start;
+use rust_runtime::state;
+use rust_runtime::update;
 
 #[derive(Debug, Parser)]
 struct Opts {
-    #[clap(short, long, default_value = "/run/youki")]
-    root: PathBuf,
+    // $XDG_RUNTIME_DIR/rust-runtime when rootless, /run/youki otherwise
+    #[clap(short, long)]
+    root: Option<PathBuf>,
     #[clap(short, long)]
     log: Option<PathBuf>,
     #[clap(long)]
@@ -26,16 +41,56 @@ enum Commands {
     Create(create::Create),
     #[command(about = "")]
     Start(start::Start),
+    #[command(about = "Dump the state of a running container with CRIU")]
+    Checkpoint(checkpoint::Checkpoint),
+    #[command(about = "Delete a container and release its resources")]
+    Delete(delete::Delete),
+    #[command(about = "Print the resource usage and the OOM events of a container")]
+    Events(events::Events),
+    #[command(about = "Execute a process inside a running container")]
+    Exec(exec::Exec),
+    #[command(about = "Send a signal to the container's processes")]
+    Kill(kill::Kill),
+    #[command(about = "List containers")]
+    List(list::List),
+    #[command(about = "Suspend every process of a container")]
+    Pause(pause::Pause),
+    #[command(about = "List the processes running inside a container")]
+    Ps(ps::Ps),
+    #[command(about = "Restore a container from the images of a checkpoint")]
+    Restore(restore::Restore),
+    #[command(about = "Resume every process of a paused container")]
+    Resume(resume::Resume),
+    #[command(about = "Create and start a container")]
+    Run(run::Run),
+    #[command(about = "Output the state of a container")]
+    State(state::State),
+    #[command(about = "Change the resource limits of a container")]
+    Update(update::Update),
 }
 
 fn main() -> Result<()> {
     let opts = Opts::parse();
     init_logger();
-    let root_path = PathBuf::from(&opts.root);
-    fs::create_dir_all(&root_path)?;
+    let root = opts.root.unwrap_or_else(rootless::default_root_path);
+    fs::create_dir_all(&root)?;
+    let root_path = fs::canonicalize(&root)?;
 
     match opts.cmd {
         Commands::Create(create) => create.exec(root_path),
         Commands::Start(start) => start.exec(root_path),
+        Commands::Checkpoint(checkpoint) => checkpoint.exec(root_path),
+        Commands::Delete(delete) => delete.exec(root_path),
+        Commands::Events(events) => events.exec(root_path),
+        Commands::Exec(exec) => exec.exec(root_path),
+        Commands::Kill(kill) => kill.exec(root_path),
+        Commands::List(list) => list.exec(root_path),
+        Commands::Pause(pause) => pause.exec(root_path),
+        Commands::Ps(ps) => ps.exec(root_path),
+        Commands::Restore(restore) => restore.exec(root_path),
+        Commands::Resume(resume) => resume.exec(root_path),
+        Commands::Run(run) => run.exec(root_path),
+        Commands::State(state) => state.exec(root_path),
+        Commands::Update(update) => update.exec(root_path),
     }
 }
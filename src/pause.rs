@@ -0,0 +1,36 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use clap::Args;
+
+use crate::cgroups::FreezerState;
+use crate::container::{Container, ContainerStatus};
+
+#[derive(Debug, Args)]
+pub struct Pause {
+    pub container_id: String,
+}
+
+impl Pause {
+    pub fn exec(&self, root_path: PathBuf) -> Result<()> {
+        let container_root = root_path.join(&self.container_id);
+        if !container_root.exists() {
+            bail!("{} doesn't exists.", self.container_id)
+        }
+        let container = Container::load(container_root)?.refresh_status()?;
+        if !container.status().can_pause() {
+            let err_msg = format!(
+                "{} could not be paused because it was {:?}",
+                container.id(),
+                container.status()
+            );
+            log::error!("{}", err_msg);
+            bail!(err_msg);
+        }
+
+        container.cgroup_manager()?.freeze(FreezerState::Frozen)?;
+        log::debug!("Paused {}", container.id());
+        container.update_status(ContainerStatus::Paused)?.save()?;
+        Ok(())
+    }
+}
@@ -41,15 +41,16 @@ impl ChildProcess {
             "child send to parent {:?}",
             (Message::ChildReady as u8).to_be_bytes()
         );
-        self.write_message_for_parent(Message::ChildReady)?;
-        self.sender_for_parent
-            .write_all(&(init_pid.as_raw()).to_be_bytes())?;
+        self.write_message_for_parent(Message::ChildReady, &init_pid.as_raw().to_be_bytes())?;
         Ok(())
     }
 
-    fn write_message_for_parent(&mut self, msg: Message) -> Result<()> {
-        self.sender_for_parent
-            .write_all(&(msg as u8).to_be_bytes())?;
+    fn write_message_for_parent(&mut self, msg: Message, payload: &[u8]) -> Result<()> {
+        // The message and its payload must be written at once, because the parent reads
+        // them back from a non-blocking pipe as soon as it becomes readable.
+        let mut buf = (msg as u8).to_be_bytes().to_vec();
+        buf.extend_from_slice(payload);
+        self.sender_for_parent.write_all(&buf)?;
         Ok(())
     }
 
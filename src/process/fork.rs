@@ -6,21 +6,27 @@ use anyhow::bail;
 use anyhow::Result;
 use libc::exit;
 use nix::sched;
+use nix::sys::signal::{self, Signal};
 use nix::sys::wait::{waitpid, WaitStatus};
 use nix::unistd;
 
+use crate::cgroups::{self, CgroupManager};
 use crate::cond::Cond;
-use crate::container::{Container, ContainerStatus};
+use crate::container::Container;
 use crate::process::{child, init, parent, Process};
 use crate::spec;
+use crate::userns;
+use crate::utils;
 
 pub fn fork_first<P: AsRef<Path>>(
     pid_file: Option<P>,
     userns: bool,
     linux: &spec::Linux,
     container: &Container,
+    cmanager: &dyn CgroupManager,
 ) -> Result<Process> {
     let ccond = Cond::new()?;
+    let pcond = Cond::new()?;
 
     let (mut parent, sender_for_parent) = parent::ParentProcess::new()?;
     log::debug!("Create Parent Process");
@@ -32,6 +38,8 @@ pub fn fork_first<P: AsRef<Path>>(
         log::debug!("Call fork systemcall");
         match unistd::fork()? {
             unistd::ForkResult::Child => {
+                // run blocks them in the runtime, not in the container
+                utils::unblock_forwarded_signals()?;
                 if let Some(ref r) = linux.resources {
                     if let Some(adj) = r.oom_score_adj {
                         let mut f = fs::File::create("/proc/self/oom_score_adj")?;
@@ -42,8 +50,15 @@ pub fn fork_first<P: AsRef<Path>>(
                 if userns {
                     sched::unshare(sched::CloneFlags::CLONE_NEWUSER)?;
                     log::debug!("Unshare !");
+                    // an unprivileged parent can only write the id maps of a dumpable process
+                    prctl::set_dumpable(true).unwrap();
                 }
                 ccond.notify()?;
+                // the parent maps our ids and moves us into the cgroup before the setup goes on
+                pcond.wait()?;
+                if userns {
+                    prctl::set_dumpable(false).unwrap();
+                }
 
                 Ok(Process::Child(child))
             }
@@ -51,17 +66,26 @@ pub fn fork_first<P: AsRef<Path>>(
                 log::debug!("Wait");
                 ccond.wait()?;
 
+                if userns {
+                    if let Err(e) = userns::write_id_mappings(child, linux) {
+                        let _ = signal::kill(child, Signal::SIGKILL);
+                        return Err(e);
+                    }
+                }
+
+                let resources = cgroups::container_resources(linux);
+                if let Err(e) = cmanager.apply(&resources, child) {
+                    let _ = signal::kill(child, Signal::SIGKILL);
+                    return Err(e);
+                }
+                log::debug!("Apply cgroup to {:?}", child);
+                pcond.notify()?;
+
                 log::debug!("Wait for child ready");
+                // the creation goes on with the hooks of the runtime namespace
                 let init_pid = parent.wait_for_child_ready()?;
-                container
-                    .update_status(ContainerStatus::Created)?
-                    .set_pid(init_pid)
-                    .save()?;
-                log::debug!(
-                    "Save container status: {:?} in {:?}",
-                    container,
-                    container.root
-                );
+                container.set_pid(init_pid).save()?;
+                log::debug!("Save init pid {:?} in {:?}", init_pid, container.root);
 
                 if let Some(pid_file) = pid_file {
                     fs::write(&pid_file, format!("{}", child))?;
@@ -93,7 +117,7 @@ pub fn fork_init(mut child_process: child::ChildProcess) -> Result<Process> {
                     }
                     WaitStatus::Signaled(pid, status, _) => {
                         log::debug!("signaled pid: {:?}, status: {:?}", pid, status);
-                        exit(0);
+                        exit(128 + status as i32);
                     }
                     _ => bail!("abnormal exited!"),
                 }
@@ -0,0 +1,63 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use clap::Args;
+use procfs::process::Process;
+
+use crate::container::{Container, ContainerStatus};
+use crate::list::{print_table, Format};
+
+#[derive(Debug, Args)]
+pub struct Ps {
+    #[clap(short, long, value_enum, default_value = "table")]
+    format: Format,
+    pub container_id: String,
+}
+
+impl Ps {
+    pub fn exec(&self, root_path: PathBuf) -> Result<()> {
+        let container_root = root_path.join(&self.container_id);
+        if !container_root.exists() {
+            bail!("{} doesn't exists.", self.container_id)
+        }
+        let container = Container::load(container_root)?.refresh_status()?;
+        if container.status() == ContainerStatus::Stopped {
+            bail!("{} is stopped", container.id())
+        }
+
+        let mut pids = container.processes()?;
+        pids.sort();
+        match self.format {
+            Format::Json => {
+                let pids: Vec<i32> = pids.iter().map(|pid| pid.as_raw()).collect();
+                println!("{}", serde_json::to_string(&pids)?);
+            }
+            Format::Table => {
+                let ticks = procfs::ticks_per_second()? as u64;
+                let mut rows = Vec::new();
+                for pid in pids {
+                    // the process may have exited since the pids were collected
+                    let proc = match Process::new(pid.as_raw()) {
+                        Ok(proc) => proc,
+                        Err(_) => continue,
+                    };
+                    let cmd = match proc.cmdline() {
+                        Ok(cmdline) if !cmdline.is_empty() => cmdline.join(" "),
+                        _ => format!("[{}]", proc.stat.comm),
+                    };
+                    let secs = (proc.stat.utime + proc.stat.stime) / ticks;
+                    rows.push(vec![
+                        proc.owner.to_string(),
+                        proc.pid.to_string(),
+                        proc.stat.ppid.to_string(),
+                        proc.stat.state.to_string(),
+                        format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60),
+                        cmd,
+                    ]);
+                }
+                print_table(&["UID", "PID", "PPID", "STAT", "TIME", "CMD"], &rows);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+use std::fs;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use clap::Args;
+use nix::fcntl::{self, OFlag};
+use nix::mount::{self, MntFlags, MsFlags};
+use nix::sys::stat::Mode;
+use nix::unistd;
+
+use crate::cgroups::{self, CgroupManager};
+use crate::container::{Container, ContainerStatus};
+use crate::criu::{self, Criu, CriuOpts, Response};
+use crate::spec;
+use crate::tty;
+use crate::utils;
+
+#[derive(Debug, Args)]
+pub struct Restore {
+    // the images written by checkpoint
+    #[clap(long)]
+    image_path: PathBuf,
+    #[clap(short, long, default_value = ".")]
+    bundle: PathBuf,
+    #[clap(short, long)]
+    console_socket: Option<String>,
+    #[clap(short, long)]
+    pid_file: Option<String>,
+    #[clap(long, default_value = "criu")]
+    criu: PathBuf,
+    pub container_id: String,
+}
+
+impl Restore {
+    pub fn exec(&self, root_path: PathBuf) -> Result<()> {
+        let container_dir = root_path.join(&self.container_id);
+        if container_dir.exists() {
+            bail!("{} already exists", self.container_id)
+        }
+        let bundle = fs::canonicalize(&self.bundle)?;
+        unistd::chdir(&bundle)?;
+        let spec = spec::Spec::load("config.json")?;
+        let linux = match &spec.linux {
+            Some(linux) => linux,
+            None => bail!("the spec of {} has no linux section", self.container_id),
+        };
+        let rootfs = fs::canonicalize(&spec.root.path)?;
+        let images_dir = fs::canonicalize(&self.image_path)?;
+
+        fs::create_dir(&container_dir)?;
+        let container_dir = fs::canonicalize(container_dir)?;
+        unistd::chdir(&*container_dir)?;
+        log::debug!("Restore: {:?}", container_dir);
+
+        let container = Container::new(
+            &self.container_id,
+            ContainerStatus::Creating,
+            None,
+            bundle.to_str().unwrap(),
+            &container_dir,
+        )?
+        .set_annotations(spec.annotations.clone());
+        container.save()?;
+
+        let cgroup_path = cgroups::get_cgroup_path(&linux.cgroups_path, container.id());
+        container.save_cgroup_path(&cgroup_path)?;
+        let cmanager = cgroups::create_cgroup_manager(&cgroup_path)?;
+        let pid = match self.restore(
+            &spec,
+            linux,
+            &rootfs,
+            &images_dir,
+            &container,
+            cmanager.as_ref(),
+        ) {
+            Ok(pid) => pid,
+            Err(e) => {
+                // leave nothing behind, so that the restore can be tried again
+                let _ = cmanager.remove();
+                // unless the rootfs could not be unmounted from criu-root
+                if !container_dir.join("criu-root").exists() {
+                    let _ = fs::remove_dir_all(&container_dir);
+                }
+                return Err(e);
+            }
+        };
+
+        container
+            .set_pid(pid)
+            .update_status(ContainerStatus::Running)?
+            .save()?;
+        if let Some(pid_file) = &self.pid_file {
+            fs::write(pid_file, format!("{}", pid))?;
+        }
+        log::debug!("Restored {} as {}", container.id(), pid);
+        Ok(())
+    }
+
+    fn restore(
+        &self,
+        spec: &spec::Spec,
+        linux: &spec::Linux,
+        rootfs: &Path,
+        images_dir: &Path,
+        container: &Container,
+        cmanager: &dyn CgroupManager,
+    ) -> Result<i32> {
+        let csocketfd = match &self.console_socket {
+            Some(console_socket) => {
+                Some(tty::load_console_sockets(&container.root, console_socket)?.0)
+            }
+            None => None,
+        };
+
+        // left open across exec for CRIU
+        let images_dir_fd = fcntl::open(
+            images_dir,
+            OFlag::O_DIRECTORY | OFlag::O_RDONLY,
+            Mode::empty(),
+        )?;
+        // CRIU restores the mounts of the container on top of a root it can't see replaced
+        let criu_root = container.root.join("criu-root");
+        let opts = CriuOpts {
+            images_dir_fd,
+            log_file: "restore.log".to_string(),
+            notify_scripts: true,
+            root: Some(criu_root.clone()),
+            ext_mnt: criu::restore_mounts(spec),
+            manage_cgroups: true,
+            cg_root: Some(container.cgroup_path()?),
+            rst_sibling: true,
+            inherit_fd: criu::inherited_namespaces(spec)?,
+            orphan_pts_master: csocketfd.is_some(),
+            ..Default::default()
+        };
+
+        // the root is unmounted whatever happens, before the container directory is removed
+        fs::create_dir(&criu_root)?;
+        if let Err(e) = mount::mount(
+            Some(rootfs),
+            &criu_root,
+            None::<&str>,
+            MsFlags::MS_BIND | MsFlags::MS_REC,
+            None::<&str>,
+        ) {
+            let _ = fs::remove_dir(&criu_root);
+            return Err(e.into());
+        }
+        let result = run_criu(
+            &self.criu,
+            &opts,
+            linux,
+            cmanager,
+            csocketfd.as_ref().map(|fd| fd.as_raw_fd()),
+        );
+        let _ = unistd::close(images_dir_fd);
+        for (_, fd) in &opts.inherit_fd {
+            let _ = unistd::close(*fd);
+        }
+
+        let cleanup = mount::umount2(&criu_root, MntFlags::MNT_DETACH)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| Ok(fs::remove_dir(&criu_root)?));
+        // the failure of CRIU comes first
+        if let (Err(_), Err(e)) = (&result, &cleanup) {
+            log::warn!("could not remove {:?}: {}", criu_root, e);
+        }
+        let response = result?;
+        cleanup?;
+        match response.restored_pid {
+            Some(pid) => Ok(pid),
+            None => bail!("CRIU didn't report the pid of the restored container"),
+        }
+    }
+}
+
+fn run_criu(
+    criu_path: &Path,
+    opts: &CriuOpts,
+    linux: &spec::Linux,
+    cmanager: &dyn CgroupManager,
+    csocketfd: Option<i32>,
+) -> Result<Response> {
+    let mut criu = Criu::start(criu_path)?;
+    // the restored processes are created in the cgroup of CRIU
+    if let Some(pid) = criu.pid() {
+        cmanager.apply(&cgroups::container_resources(linux), pid)?;
+    }
+    criu.request(criu::REQ_RESTORE, opts, |script, fd| {
+        // CRIU hands over the master of the console it opened for the restored processes
+        if let (Some(csocketfd), Some(fd)) = (csocketfd, fd) {
+            if script == "orphan-pts-master" {
+                utils::send_fds(csocketfd, b"/dev/ptmx", &[fd])?;
+            }
+        }
+        Ok(())
+    })
+}
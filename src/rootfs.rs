@@ -11,20 +11,23 @@ use nix::mount::MsFlags;
 use nix::mount::*;
 use nix::sys::stat::{mknod, umask};
 use nix::sys::stat::{Mode, SFlag};
+use nix::sys::statvfs::{self, FsFlags};
 use nix::unistd::{chdir, chown, close, fchdir, getcwd, pivot_root};
 use nix::unistd::{Gid, Uid};
 use nix::NixPath;
+use procfs::process::Process;
 
-use crate::spec::{LinuxDevice, LinuxDeviceType, Mount, Spec};
+use crate::cgroups;
+use crate::spec::{Linux, LinuxDevice, LinuxDeviceType, Mount, Spec};
 
-fn default_devices() -> Vec<LinuxDevice> {
+pub fn default_devices() -> Vec<LinuxDevice> {
     vec![
         LinuxDevice {
             path: "/dev/null".to_string(),
             typ: LinuxDeviceType::C,
             major: 1,
             minor: 3,
-            file_mode: Some(0o066),
+            file_mode: Some(0o666),
             uid: None,
             gid: None,
         },
@@ -33,7 +36,7 @@ fn default_devices() -> Vec<LinuxDevice> {
             typ: LinuxDeviceType::C,
             major: 1,
             minor: 5,
-            file_mode: Some(0o066),
+            file_mode: Some(0o666),
             uid: None,
             gid: None,
         },
@@ -42,7 +45,7 @@ fn default_devices() -> Vec<LinuxDevice> {
             typ: LinuxDeviceType::C,
             major: 1,
             minor: 7,
-            file_mode: Some(0o066),
+            file_mode: Some(0o666),
             uid: None,
             gid: None,
         },
@@ -51,7 +54,7 @@ fn default_devices() -> Vec<LinuxDevice> {
             typ: LinuxDeviceType::C,
             major: 5,
             minor: 0,
-            file_mode: Some(0o066),
+            file_mode: Some(0o666),
             uid: None,
             gid: None,
         },
@@ -60,7 +63,7 @@ fn default_devices() -> Vec<LinuxDevice> {
             typ: LinuxDeviceType::C,
             major: 1,
             minor: 9,
-            file_mode: Some(0o066),
+            file_mode: Some(0o666),
             uid: None,
             gid: None,
         },
@@ -69,7 +72,7 @@ fn default_devices() -> Vec<LinuxDevice> {
             typ: LinuxDeviceType::C,
             major: 1,
             minor: 8,
-            file_mode: Some(0o066),
+            file_mode: Some(0o666),
             uid: None,
             gid: None,
         },
@@ -103,8 +106,7 @@ pub async fn prepare_rootfs(spec: &Spec, rootfs: &PathBuf, bind_devices: bool) -
         let (flags, data) = parse_mount(m);
         // log::debug!("mount {:?}", m);
         if m.typ == "cgroup" {
-            log::warn!("A feature of cgoup is unimplemented.");
-            // skip
+            mount_cgroup(m, rootfs, flags, &data, &linux.mount_label, bind_devices)?;
         } else if m.destination == PathBuf::from("/dev") {
             mount_from(
                 m,
@@ -130,6 +132,82 @@ pub async fn prepare_rootfs(spec: &Spec, rootfs: &PathBuf, bind_devices: bool) -
     Ok(())
 }
 
+// Done once the rootfs is the root, so that the symlinks in the paths cannot lead out of it.
+pub fn protect_paths(linux: &Linux) -> Result<()> {
+    for path in &linux.masked_paths {
+        mask_path(Path::new(path), &linux.mount_label)?;
+    }
+    for path in &linux.readonly_paths {
+        readonly_path(Path::new(path))?;
+    }
+    Ok(())
+}
+
+// Hide a file behind /dev/null, or a directory behind an empty tmpfs.
+fn mask_path(path: &Path, label: &str) -> Result<()> {
+    log::debug!("mask {:?}", path);
+    let result = mount(
+        Some("/dev/null"),
+        path,
+        None::<&str>,
+        MsFlags::MS_BIND,
+        None::<&str>,
+    );
+    match result {
+        Ok(()) => Ok(()),
+        // the path does not exist in the container
+        Err(nix::Error::Sys(Errno::ENOENT)) => Ok(()),
+        Err(nix::Error::Sys(Errno::ENOTDIR)) => {
+            let data = if label.is_empty() {
+                String::new()
+            } else {
+                format!("context=\"{}\"", label)
+            };
+            mount(
+                Some("tmpfs"),
+                path,
+                Some("tmpfs"),
+                MsFlags::MS_RDONLY,
+                Some(data.as_str()),
+            )?;
+            Ok(())
+        }
+        Err(e) => bail!("failed to mask {:?}: {}", path, e),
+    }
+}
+
+fn readonly_path(path: &Path) -> Result<()> {
+    log::debug!("make {:?} read-only", path);
+    match mount(
+        Some(path),
+        path,
+        None::<&str>,
+        MsFlags::MS_BIND | MsFlags::MS_REC,
+        None::<&str>,
+    ) {
+        Ok(()) => {}
+        Err(nix::Error::Sys(Errno::ENOENT)) => return Ok(()),
+        Err(e) => bail!("failed to bind mount {:?}: {}", path, e),
+    }
+
+    // the flags locked by a user namespace have to be kept on remount
+    let mut flags = MsFlags::MS_BIND | MsFlags::MS_REMOUNT | MsFlags::MS_REC | MsFlags::MS_RDONLY;
+    let current = statvfs::statvfs(path)?.flags();
+    for &(flag, ms_flag) in [
+        (FsFlags::ST_NOSUID, MsFlags::MS_NOSUID),
+        (FsFlags::ST_NODEV, MsFlags::MS_NODEV),
+        (FsFlags::ST_NOEXEC, MsFlags::MS_NOEXEC),
+    ]
+    .iter()
+    {
+        if current.contains(flag) {
+            flags |= ms_flag;
+        }
+    }
+    mount(Some(path), path, None::<&str>, flags, None::<&str>)?;
+    Ok(())
+}
+
 fn setup_ptmx(rootfs: &PathBuf) -> Result<()> {
     if let Err(e) = remove_file(rootfs.join("dev/ptmx")) {
         if e.kind() != ::std::io::ErrorKind::NotFound {
@@ -142,10 +220,6 @@ fn setup_ptmx(rootfs: &PathBuf) -> Result<()> {
 }
 
 fn setup_default_symlinks(rootfs: &PathBuf) -> Result<()> {
-    if Path::new("/proc/kcore").exists() {
-        symlink("/proc/kcore", "dev/kcore")?;
-    }
-
     let defaults = [
         ("/proc/self/fd", "dev/fd"),
         ("/proc/self/fd/0", "dev/stdin"),
@@ -218,7 +292,7 @@ async fn mknod_dev(dev: &LinuxDevice) -> Result<()> {
     Ok(())
 }
 
-fn mount_from(m: &Mount, rootfs: &PathBuf, flags: MsFlags, data: &str, label: &str) -> Result<()> {
+fn mount_from(m: &Mount, rootfs: &Path, flags: MsFlags, data: &str, label: &str) -> Result<()> {
     let d;
     if !label.is_empty() && m.typ != "proc" && m.typ != "sysfs" {
         if data.is_empty() {
@@ -292,6 +366,119 @@ fn mount_from(m: &Mount, rootfs: &PathBuf, flags: MsFlags, data: &str, label: &s
     Ok(())
 }
 
+// In a user namespace the cgroup filesystems can't be mounted again, so the ones of the host are
+// bound instead.
+fn mount_cgroup(
+    m: &Mount,
+    rootfs: &Path,
+    flags: MsFlags,
+    data: &str,
+    label: &str,
+    bind: bool,
+) -> Result<()> {
+    if cgroups::is_unified_hierarchy()? {
+        if bind {
+            let host = Mount {
+                destination: m.destination.clone(),
+                typ: "bind".to_string(),
+                source: PathBuf::from("/sys/fs/cgroup"),
+                options: m.options.clone(),
+            };
+            return mount_from(
+                &host,
+                rootfs,
+                flags | MsFlags::MS_BIND | MsFlags::MS_REC,
+                "",
+                label,
+            );
+        }
+        let cgroup2 = Mount {
+            destination: m.destination.clone(),
+            typ: "cgroup2".to_string(),
+            source: PathBuf::from("cgroup"),
+            options: m.options.clone(),
+        };
+        return mount_from(&cgroup2, rootfs, flags, data, label);
+    }
+
+    // cgroup v1: a tmpfs holding a mount of every hierarchy of the host
+    let tmpfs = Mount {
+        destination: m.destination.clone(),
+        typ: "tmpfs".to_string(),
+        source: PathBuf::from("tmpfs"),
+        options: Vec::new(),
+    };
+    mount_from(
+        &tmpfs,
+        rootfs,
+        flags & !MsFlags::MS_RDONLY,
+        "mode=755",
+        label,
+    )?;
+    let dest = rootfs.join(m.destination.strip_prefix("/").unwrap_or(&m.destination));
+
+    for hierarchy in Process::myself()?.mountinfo()? {
+        if hierarchy.fs_type != "cgroup" {
+            continue;
+        }
+        let name = match hierarchy.mount_point.file_name() {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => continue,
+        };
+        if bind {
+            let host = Mount {
+                destination: m.destination.join(&name),
+                typ: "bind".to_string(),
+                source: hierarchy.mount_point.clone(),
+                options: m.options.clone(),
+            };
+            mount_from(
+                &host,
+                rootfs,
+                flags | MsFlags::MS_BIND | MsFlags::MS_REC,
+                "",
+                label,
+            )?;
+        } else {
+            let subsystems: Vec<String> = hierarchy
+                .super_options
+                .iter()
+                .filter(|(key, _)| *key != "rw" && *key != "ro")
+                .map(|(key, value)| match value {
+                    Some(value) => format!("{}={}", key, value),
+                    None => key.clone(),
+                })
+                .collect();
+            let target = dest.join(&name);
+            create_dir_all(&target)?;
+            mount(
+                Some("cgroup"),
+                &target,
+                Some("cgroup"),
+                flags,
+                Some(&*subsystems.join(",")),
+            )?;
+        }
+        // co-mounted subsystems such as cpu,cpuacct are reachable under each name
+        if name.contains(',') {
+            for subsystem in name.split(',') {
+                symlink(&name, dest.join(subsystem))?;
+            }
+        }
+    }
+
+    if flags.contains(MsFlags::MS_RDONLY) {
+        mount(
+            None::<&str>,
+            &dest,
+            None::<&str>,
+            flags | MsFlags::MS_REMOUNT,
+            None::<&str>,
+        )?;
+    }
+    Ok(())
+}
+
 pub fn pivot_rootfs<P: ?Sized + NixPath>(path: &P) -> Result<()> {
     let newroot = open(path, OFlag::O_DIRECTORY | OFlag::O_RDONLY, Mode::empty())?;
 
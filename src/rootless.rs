@@ -0,0 +1,106 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use nix::unistd::{Gid, Uid};
+
+use crate::spec::{Linux, LinuxIdMapping, LinuxNamespaceType};
+
+// The runtime is rootless when it isn't run by root.
+pub fn is_rootless() -> bool {
+    !Uid::effective().is_root()
+}
+
+// The state of the containers of an unprivileged user lives in its own runtime directory.
+pub fn default_root_path() -> PathBuf {
+    if !is_rootless() {
+        return PathBuf::from("/run/youki");
+    }
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir).join("rust-runtime"),
+        None => env::temp_dir().join(format!("rust-runtime-{}", Uid::effective())),
+    }
+}
+
+// An unprivileged user can only map its own ids, or the ranges delegated to it in /etc/subuid and
+// /etc/subgid which newuidmap and newgidmap accept.
+pub fn validate(linux: &Linux) -> Result<()> {
+    if !linux
+        .namespaces
+        .iter()
+        .any(|ns| matches!(ns.typ, LinuxNamespaceType::User))
+    {
+        bail!("a rootless container needs a user namespace")
+    }
+    if linux.uid_mappings.is_empty() || linux.gid_mappings.is_empty() {
+        bail!("a rootless container needs uidMappings and gidMappings")
+    }
+
+    let uid = Uid::effective().as_raw();
+    let gid = Gid::effective().as_raw();
+    let uid_ranges = subordinate_ranges("/etc/subuid", uid)?;
+    let gid_ranges = subordinate_ranges("/etc/subgid", uid)?;
+    check_mappings("uid", &linux.uid_mappings, uid, &uid_ranges)?;
+    check_mappings("gid", &linux.gid_mappings, gid, &gid_ranges)?;
+    Ok(())
+}
+
+fn check_mappings(
+    kind: &str,
+    mappings: &[LinuxIdMapping],
+    own: u32,
+    ranges: &[(u32, u32)],
+) -> Result<()> {
+    for m in mappings {
+        let start = m.host_id as u64;
+        let end = start + m.size as u64;
+        let own_id = m.host_id == own && m.size == 1;
+        let delegated = ranges
+            .iter()
+            .any(|&(first, count)| start >= first as u64 && end <= first as u64 + count as u64);
+        if !own_id && !delegated {
+            bail!(
+                "the {} mapping {}-{} is not delegated to the user",
+                kind,
+                start,
+                end - 1
+            )
+        }
+    }
+    Ok(())
+}
+
+// The entries of /etc/subuid or /etc/subgid are name:first:count, with either the name or the
+// uid of the user.
+fn subordinate_ranges(file: &str, uid: u32) -> Result<Vec<(u32, u32)>> {
+    let content = match fs::read_to_string(file) {
+        Ok(content) => content,
+        Err(_) => return Ok(Vec::new()),
+    };
+    let name = user_name(uid);
+    let mut ranges = Vec::new();
+    for line in content.lines() {
+        let fields: Vec<&str> = line.trim().split(':').collect();
+        if fields.len() != 3 {
+            continue;
+        }
+        if fields[0] != uid.to_string() && Some(fields[0]) != name.as_deref() {
+            continue;
+        }
+        ranges.push((fields[1].parse()?, fields[2].parse()?));
+    }
+    Ok(ranges)
+}
+
+fn user_name(uid: u32) -> Option<String> {
+    let passwd = fs::read_to_string("/etc/passwd").ok()?;
+    passwd.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        if fields.len() >= 3 && fields[2] == uid.to_string() {
+            Some(fields[0].to_string())
+        } else {
+            None
+        }
+    })
+}
@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+use std::process;
+
+use anyhow::{bail, Result};
+use clap::Args;
+
+use crate::container::Container;
+use crate::create::Create;
+use crate::delete::Delete;
+use crate::start::Start;
+use crate::utils;
+
+#[derive(Debug, Args)]
+pub struct Run {
+    #[clap(short, long)]
+    detach: bool,
+    #[clap(flatten)]
+    create: Create,
+}
+
+impl Run {
+    pub fn exec(&self, root_path: PathBuf) -> Result<()> {
+        let container_id = self.create.container_id.clone();
+        // a signal received before the wait must not skip the deletion of the container
+        utils::block_forwarded_signals()?;
+        self.create.exec(root_path.clone())?;
+        Start {
+            container_id: container_id.clone(),
+        }
+        .exec(root_path.clone())?;
+        if self.detach {
+            return Ok(());
+        }
+
+        let container = Container::load(root_path.join(&container_id))?;
+        let init_pid = match container.pid() {
+            Some(pid) => pid,
+            None => bail!("{} has no init process", container_id),
+        };
+        let exit_code = utils::wait_for_child(init_pid)?;
+        log::debug!("{} exited with {}", container_id, exit_code);
+
+        Delete {
+            force: true,
+            container_id,
+        }
+        .exec(root_path)?;
+        process::exit(exit_code);
+    }
+}
@@ -0,0 +1,56 @@
+use anyhow::{bail, Result};
+use libc::sock_filter;
+use nix::errno::Errno;
+
+// Offsets in struct seccomp_data.
+pub const NR_OFFSET: u32 = 0;
+pub const ARCH_OFFSET: u32 = 4;
+const ARGS_OFFSET: u32 = 16;
+
+// Offsets of the low and high halves of a syscall argument.
+pub fn arg_offsets(index: usize) -> (u32, u32) {
+    let offset = ARGS_OFFSET + 8 * index as u32;
+    if cfg!(target_endian = "little") {
+        (offset, offset + 4)
+    } else {
+        (offset + 4, offset)
+    }
+}
+
+pub fn stmt(code: u32, k: u32) -> sock_filter {
+    sock_filter {
+        code: code as u16,
+        jt: 0,
+        jf: 0,
+        k,
+    }
+}
+
+pub fn jump(code: u32, k: u32, jt: u8, jf: u8) -> sock_filter {
+    sock_filter {
+        code: code as u16,
+        jt,
+        jf,
+        k,
+    }
+}
+
+// Returns the notify listener when flags has SECCOMP_FILTER_FLAG_NEW_LISTENER.
+pub fn load(filter: &[sock_filter], flags: libc::c_ulong) -> Result<libc::c_long> {
+    let prog = libc::sock_fprog {
+        len: filter.len() as u16,
+        filter: filter.as_ptr() as *mut sock_filter,
+    };
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_seccomp,
+            libc::SECCOMP_SET_MODE_FILTER,
+            flags,
+            &prog as *const libc::sock_fprog,
+        )
+    };
+    if ret < 0 {
+        bail!("failed to load the seccomp filter: {}", Errno::last());
+    }
+    Ok(ret)
+}
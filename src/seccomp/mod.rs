@@ -0,0 +1,630 @@
+mod bpf;
+#[cfg(target_arch = "x86_64")]
+mod compat;
+mod syscalls;
+
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::UnixStream;
+
+use anyhow::{anyhow, bail, Result};
+use libc::{
+    sock_filter, BPF_ABS, BPF_ALU, BPF_AND, BPF_JA, BPF_JEQ, BPF_JGE, BPF_JGT, BPF_JMP, BPF_K,
+    BPF_LD, BPF_RET, BPF_W,
+};
+
+use nix::sys::socket::{self, AddressFamily, SockFlag, SockType};
+use nix::unistd;
+use serde::Serialize;
+
+use crate::container::State;
+use crate::spec::{
+    Arch, LinuxSeccomp, LinuxSeccompAction, LinuxSeccompArg, LinuxSeccompFlag, LinuxSeccompOperator,
+};
+use crate::utils;
+
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH_X86_64: u32 = 0xc000_003e;
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH_I386: u32 = 0x4000_0003;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH_AARCH64: u32 = 0xc000_00b7;
+
+// x32 syscalls share the audit architecture of x86_64 and have this bit set in their number.
+const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+// Syscalls made through an ABI that the profile does not list fail with ENOSYS.
+const RET_FOREIGN_ABI: u32 = libc::SECCOMP_RET_ERRNO | libc::ENOSYS as u32;
+
+// An ABI of the syscalls the filter checks.
+struct Abi {
+    arch: Arch,
+    audit_arch: u32,
+    // with Some, only the syscalls with, or without, X32_SYSCALL_BIT belong to the ABI
+    x32: Option<bool>,
+    lookup: fn(&str) -> Option<libc::c_long>,
+}
+
+#[cfg(target_arch = "x86_64")]
+fn abi(arch: Arch) -> Result<Abi> {
+    let (audit_arch, x32, lookup): (_, _, fn(&str) -> _) = match arch {
+        Arch::ScmpArchNative | Arch::ScmpArchX86_64 => {
+            (AUDIT_ARCH_X86_64, Some(false), syscalls::lookup)
+        }
+        Arch::ScmpArchX86 => (AUDIT_ARCH_I386, None, compat::lookup_x86),
+        Arch::ScmpArchX32 => (AUDIT_ARCH_X86_64, Some(true), compat::lookup_x32),
+        _ => bail!("seccomp architecture {:?} is not supported on x86_64", arch),
+    };
+    Ok(Abi {
+        arch,
+        audit_arch,
+        x32,
+        lookup,
+    })
+}
+
+#[cfg(target_arch = "aarch64")]
+fn abi(arch: Arch) -> Result<Abi> {
+    match arch {
+        Arch::ScmpArchNative | Arch::ScmpArchAarch64 => Ok(Abi {
+            arch,
+            audit_arch: AUDIT_ARCH_AARCH64,
+            x32: None,
+            lookup: syscalls::lookup,
+        }),
+        _ => bail!(
+            "seccomp architecture {:?} is not supported on aarch64",
+            arch
+        ),
+    }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn abi(_arch: Arch) -> Result<Abi> {
+    bail!("seccomp is not supported on {}", std::env::consts::ARCH)
+}
+
+// The message sent with the notify listener to the agent at listenerPath.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ContainerProcessState<'a> {
+    oci_version: &'a str,
+    fds: Vec<&'a str>,
+    pid: i32,
+    metadata: &'a str,
+    state: &'a State,
+}
+
+const SECCOMP_FD_NAME: &str = "seccompFd";
+
+fn has_notify(seccomp: &LinuxSeccomp) -> bool {
+    seccomp
+        .syscalls
+        .iter()
+        .any(|syscall| syscall.action == LinuxSeccompAction::ScmpActNotify)
+}
+
+// The container process cannot reach listenerPath once it is in the container mount namespace,
+// so it passes the notify listener to the runtime process through a socket pair. Returns the
+// end of the runtime process first, or nothing when the profile has no SCMP_ACT_NOTIFY rule.
+pub fn listener_sockets(seccomp: Option<&LinuxSeccomp>) -> Result<Option<(RawFd, RawFd)>> {
+    let seccomp = match seccomp {
+        Some(seccomp) if has_notify(seccomp) => seccomp,
+        _ => return Ok(None),
+    };
+    if seccomp.listener_path.is_none() {
+        bail!("listenerPath is required by SCMP_ACT_NOTIFY");
+    }
+    let sockets = socket::socketpair(
+        AddressFamily::Unix,
+        SockType::Stream,
+        None,
+        SockFlag::SOCK_CLOEXEC,
+    )?;
+    Ok(Some(sockets))
+}
+
+// Load the filter in the container process, then pass the notify listener to the runtime
+// process if the profile has one.
+pub fn initialize_seccomp(seccomp: &LinuxSeccomp, listener_socket: Option<RawFd>) -> Result<()> {
+    if seccomp.default_action == LinuxSeccompAction::ScmpActNotify {
+        bail!("SCMP_ACT_NOTIFY cannot be the default action");
+    }
+    let filter = build_filter(seccomp)?;
+    log::debug!("seccomp filter: {} instructions", filter.len());
+
+    let mut flags = 0;
+    for flag in &seccomp.flags {
+        flags |= match flag {
+            LinuxSeccompFlag::SeccompFilterFlagTsync => libc::SECCOMP_FILTER_FLAG_TSYNC,
+            LinuxSeccompFlag::SeccompFilterFlagLog => libc::SECCOMP_FILTER_FLAG_LOG,
+            LinuxSeccompFlag::SeccompFilterFlagSpecAllow => libc::SECCOMP_FILTER_FLAG_SPEC_ALLOW,
+        };
+    }
+    let listener_socket = match listener_socket {
+        Some(listener_socket) if has_notify(seccomp) => listener_socket,
+        _ => {
+            bpf::load(&filter, flags)?;
+            return Ok(());
+        }
+    };
+
+    let listener = bpf::load(&filter, flags | libc::SECCOMP_FILTER_FLAG_NEW_LISTENER)? as RawFd;
+    utils::send_fds(listener_socket, SECCOMP_FD_NAME.as_bytes(), &[listener])?;
+    unistd::close(listener)?;
+    unistd::close(listener_socket)?;
+    Ok(())
+}
+
+// Receive the notify listener from the container process, and send it to the agent at
+// listenerPath along with the state of the container and the pid of the process.
+pub fn forward_listener(
+    seccomp: &LinuxSeccomp,
+    listener_socket: RawFd,
+    state: &State,
+    pid: i32,
+) -> Result<()> {
+    let listener = utils::recv_fd(listener_socket)?;
+    unistd::close(listener_socket)?;
+
+    let listener_path = seccomp.listener_path.as_ref().unwrap();
+    let process_state = ContainerProcessState {
+        oci_version: &state.oci_version,
+        fds: vec![SECCOMP_FD_NAME],
+        pid,
+        metadata: seccomp.listener_metadata.as_deref().unwrap_or_default(),
+        state,
+    };
+    let message = serde_json::to_vec(&process_state)?;
+    log::debug!("send seccomp listener to {:?}", listener_path);
+
+    let result = UnixStream::connect(listener_path)
+        .map_err(|e| anyhow!("failed to connect to {:?}: {}", listener_path, e))
+        .and_then(|stream| utils::send_fds(stream.as_raw_fd(), &message, &[listener]));
+    unistd::close(listener)?;
+    result
+}
+
+fn to_action(action: LinuxSeccompAction, errno_ret: Option<u32>) -> u32 {
+    let errno = errno_ret.unwrap_or(libc::EPERM as u32) & libc::SECCOMP_RET_DATA;
+    match action {
+        LinuxSeccompAction::ScmpActKill | LinuxSeccompAction::ScmpActKillThread => {
+            libc::SECCOMP_RET_KILL_THREAD
+        }
+        LinuxSeccompAction::ScmpActKillProcess => libc::SECCOMP_RET_KILL_PROCESS,
+        LinuxSeccompAction::ScmpActTrap => libc::SECCOMP_RET_TRAP,
+        LinuxSeccompAction::ScmpActErrno => libc::SECCOMP_RET_ERRNO | errno,
+        LinuxSeccompAction::ScmpActTrace => libc::SECCOMP_RET_TRACE | errno,
+        LinuxSeccompAction::ScmpActAllow => libc::SECCOMP_RET_ALLOW,
+        LinuxSeccompAction::ScmpActLog => libc::SECCOMP_RET_LOG,
+        LinuxSeccompAction::ScmpActNotify => libc::SECCOMP_RET_USER_NOTIF,
+    }
+}
+
+// The filter goes to the rules of the ABI of the syscall, which it checks one after the other,
+// in the order of the profile, and returns the action of the first one matching the syscall.
+fn build_filter(seccomp: &LinuxSeccomp) -> Result<Vec<sock_filter>> {
+    let default_action = to_action(seccomp.default_action, seccomp.default_errno_ret);
+    let architectures = if seccomp.architectures.is_empty() {
+        vec![Arch::ScmpArchNative]
+    } else {
+        seccomp.architectures.clone()
+    };
+    let mut abis: Vec<Abi> = Vec::new();
+    for arch in architectures {
+        let abi = abi(arch)?;
+        if !abis
+            .iter()
+            .any(|other| other.audit_arch == abi.audit_arch && other.x32 == abi.x32)
+        {
+            abis.push(abi);
+        }
+    }
+
+    let mut filter = Vec::new();
+    for abi in &abis {
+        let rules = build_rules(seccomp, abi, default_action)?;
+        filter.push(bpf::stmt(BPF_LD | BPF_W | BPF_ABS, bpf::ARCH_OFFSET));
+        match abi.x32 {
+            None => filter.push(bpf::jump(BPF_JMP | BPF_JEQ | BPF_K, abi.audit_arch, 1, 0)),
+            Some(x32) => filter.extend([
+                bpf::jump(BPF_JMP | BPF_JEQ | BPF_K, abi.audit_arch, 0, 2),
+                bpf::stmt(BPF_LD | BPF_W | BPF_ABS, bpf::NR_OFFSET),
+                bpf::jump(
+                    BPF_JMP | BPF_JGE | BPF_K,
+                    X32_SYSCALL_BIT,
+                    x32 as u8,
+                    !x32 as u8,
+                ),
+            ]),
+        }
+        // skip the rules of the ABI, they are too long for a conditional jump
+        filter.push(bpf::stmt(BPF_JMP | BPF_JA | BPF_K, rules.len() as u32));
+        filter.extend(rules);
+    }
+    filter.push(bpf::stmt(BPF_RET | BPF_K, RET_FOREIGN_ABI));
+
+    if filter.len() > libc::BPF_MAXINSNS as usize {
+        bail!(
+            "the seccomp profile needs {} instructions, more than the limit of {}",
+            filter.len(),
+            libc::BPF_MAXINSNS
+        );
+    }
+    Ok(filter)
+}
+
+fn build_rules(seccomp: &LinuxSeccomp, abi: &Abi, default_action: u32) -> Result<Vec<sock_filter>> {
+    let mut rules = Vec::new();
+    for (i, syscall) in seccomp.syscalls.iter().enumerate() {
+        let action = to_action(syscall.action, syscall.errno_ret);
+        for name in &syscall.names {
+            // A rule with the default action can only be left out when no later rule could
+            // match the syscall first.
+            let named_later = seccomp.syscalls[i + 1..]
+                .iter()
+                .any(|later| later.names.contains(name));
+            if action == default_action && !named_later {
+                continue;
+            }
+            match (abi.lookup)(name) {
+                Some(nr) => rules.extend(build_rule(nr, &syscall.args, action)?),
+                None => log::warn!(
+                    "ignoring unknown syscall {} of {:?} in the seccomp profile",
+                    name,
+                    abi.arch
+                ),
+            }
+        }
+    }
+    rules.push(bpf::stmt(BPF_RET | BPF_K, default_action));
+    Ok(rules)
+}
+
+// Where a conditional jump of a rule goes.
+#[derive(Clone, Copy)]
+enum Target {
+    // skip this many instructions
+    Skip(u8),
+    // go to the next rule, the syscall does not match this one
+    NoMatch,
+}
+
+#[derive(Default)]
+struct Rule {
+    insns: Vec<(sock_filter, Option<(Target, Target)>)>,
+}
+
+impl Rule {
+    fn stmt(&mut self, code: u32, k: u32) {
+        self.insns.push((bpf::stmt(code, k), None));
+    }
+
+    fn jump(&mut self, code: u32, k: u32, jt: Target, jf: Target) {
+        self.insns.push((bpf::jump(code, k, 0, 0), Some((jt, jf))));
+    }
+
+    fn finish(self) -> Vec<sock_filter> {
+        let len = self.insns.len();
+        let resolve = |i: usize, target| match target {
+            Target::Skip(n) => n,
+            Target::NoMatch => (len - i - 1) as u8,
+        };
+        self.insns
+            .into_iter()
+            .enumerate()
+            .map(|(i, (mut insn, targets))| {
+                if let Some((jt, jf)) = targets {
+                    insn.jt = resolve(i, jt);
+                    insn.jf = resolve(i, jf);
+                }
+                insn
+            })
+            .collect()
+    }
+}
+
+fn build_rule(nr: libc::c_long, args: &[LinuxSeccompArg], action: u32) -> Result<Vec<sock_filter>> {
+    use Target::{NoMatch, Skip};
+
+    let mut rule = Rule::default();
+    rule.stmt(BPF_LD | BPF_W | BPF_ABS, bpf::NR_OFFSET);
+    rule.jump(BPF_JMP | BPF_JEQ | BPF_K, nr as u32, Skip(0), NoMatch);
+
+    // The arguments are 64 bits wide but BPF compares 32 bits at a time: the high halves
+    // decide unless they are equal.
+    for arg in args {
+        if arg.index >= 6 {
+            bail!("invalid seccomp argument index {}", arg.index);
+        }
+        let (lo_offset, hi_offset) = bpf::arg_offsets(arg.index);
+        let (hi, lo) = ((arg.value >> 32) as u32, arg.value as u32);
+        let load_hi = |rule: &mut Rule| rule.stmt(BPF_LD | BPF_W | BPF_ABS, hi_offset);
+        let load_lo = |rule: &mut Rule| rule.stmt(BPF_LD | BPF_W | BPF_ABS, lo_offset);
+        let eq = BPF_JMP | BPF_JEQ | BPF_K;
+        let gt = BPF_JMP | BPF_JGT | BPF_K;
+        let ge = BPF_JMP | BPF_JGE | BPF_K;
+
+        match arg.op {
+            LinuxSeccompOperator::ScmpCmpEq => {
+                load_hi(&mut rule);
+                rule.jump(eq, hi, Skip(0), NoMatch);
+                load_lo(&mut rule);
+                rule.jump(eq, lo, Skip(0), NoMatch);
+            }
+            LinuxSeccompOperator::ScmpCmpNe => {
+                load_hi(&mut rule);
+                rule.jump(eq, hi, Skip(0), Skip(2));
+                load_lo(&mut rule);
+                rule.jump(eq, lo, NoMatch, Skip(0));
+            }
+            LinuxSeccompOperator::ScmpCmpGt | LinuxSeccompOperator::ScmpCmpGe => {
+                load_hi(&mut rule);
+                rule.jump(gt, hi, Skip(3), Skip(0));
+                rule.jump(eq, hi, Skip(0), NoMatch);
+                load_lo(&mut rule);
+                let code = if arg.op == LinuxSeccompOperator::ScmpCmpGt {
+                    gt
+                } else {
+                    ge
+                };
+                rule.jump(code, lo, Skip(0), NoMatch);
+            }
+            LinuxSeccompOperator::ScmpCmpLt | LinuxSeccompOperator::ScmpCmpLe => {
+                load_hi(&mut rule);
+                rule.jump(gt, hi, NoMatch, Skip(0));
+                rule.jump(eq, hi, Skip(0), Skip(2));
+                load_lo(&mut rule);
+                let code = if arg.op == LinuxSeccompOperator::ScmpCmpLt {
+                    ge
+                } else {
+                    gt
+                };
+                rule.jump(code, lo, NoMatch, Skip(0));
+            }
+            LinuxSeccompOperator::ScmpCmpMaskedEq => {
+                // value is the mask and valueTwo the expected result
+                let (datum_hi, datum_lo) = ((arg.value_two >> 32) as u32, arg.value_two as u32);
+                load_hi(&mut rule);
+                rule.stmt(BPF_ALU | BPF_AND | BPF_K, hi);
+                rule.jump(eq, datum_hi, Skip(0), NoMatch);
+                load_lo(&mut rule);
+                rule.stmt(BPF_ALU | BPF_AND | BPF_K, lo);
+                rule.jump(eq, datum_lo, Skip(0), NoMatch);
+            }
+        }
+    }
+
+    rule.stmt(BPF_RET | BPF_K, action);
+    Ok(rule.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const EPERM: u32 = libc::SECCOMP_RET_ERRNO | libc::EPERM as u32;
+
+    // Run the filter on a syscall the way the kernel does.
+    fn run(filter: &[sock_filter], arch: u32, nr: u32, args: [u64; 6]) -> u32 {
+        let mut data = Vec::new();
+        data.extend(nr.to_ne_bytes());
+        data.extend(arch.to_ne_bytes());
+        data.extend(0u64.to_ne_bytes());
+        for arg in args {
+            data.extend(arg.to_ne_bytes());
+        }
+        let load = |offset: u32| {
+            let offset = offset as usize;
+            u32::from_ne_bytes(data[offset..offset + 4].try_into().unwrap())
+        };
+
+        let (mut pc, mut a) = (0, 0);
+        loop {
+            let insn = filter[pc];
+            pc += 1;
+            let code = insn.code as u32;
+            if code == BPF_LD | BPF_W | BPF_ABS {
+                a = load(insn.k);
+            } else if code == BPF_ALU | BPF_AND | BPF_K {
+                a &= insn.k;
+            } else if code == BPF_JMP | BPF_JA | BPF_K {
+                pc += insn.k as usize;
+            } else if code == BPF_RET | BPF_K {
+                return insn.k;
+            } else {
+                let matched = match code {
+                    c if c == BPF_JMP | BPF_JEQ | BPF_K => a == insn.k,
+                    c if c == BPF_JMP | BPF_JGT | BPF_K => a > insn.k,
+                    c if c == BPF_JMP | BPF_JGE | BPF_K => a >= insn.k,
+                    _ => panic!("unexpected instruction {:#x}", code),
+                };
+                pc += if matched { insn.jt } else { insn.jf } as usize;
+            }
+        }
+    }
+
+    fn profile(profile: serde_json::Value) -> LinuxSeccomp {
+        serde_json::from_value(profile).unwrap()
+    }
+
+    fn native() -> Abi {
+        abi(Arch::ScmpArchNative).unwrap()
+    }
+
+    fn nr(name: &str) -> u32 {
+        syscalls::lookup(name).unwrap() as u32
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let filter = build_filter(&profile(json!({
+            "defaultAction": "SCMP_ACT_ERRNO",
+            "syscalls": [
+                {
+                    "names": ["clone"],
+                    "action": "SCMP_ACT_ERRNO",
+                    "args": [{
+                        "index": 0,
+                        "value": libc::CLONE_NEWUSER,
+                        "valueTwo": libc::CLONE_NEWUSER,
+                        "op": "SCMP_CMP_MASKED_EQ"
+                    }]
+                },
+                { "names": ["clone", "getpid"], "action": "SCMP_ACT_ALLOW" }
+            ]
+        })))
+        .unwrap();
+        let arch = native().audit_arch;
+        let newuser = libc::CLONE_NEWUSER as u64;
+
+        assert_eq!(
+            run(&filter, arch, nr("clone"), [newuser, 0, 0, 0, 0, 0]),
+            EPERM
+        );
+        assert_eq!(
+            run(&filter, arch, nr("clone"), [0, 0, 0, 0, 0, 0]),
+            libc::SECCOMP_RET_ALLOW
+        );
+        assert_eq!(
+            run(&filter, arch, nr("getpid"), [0; 6]),
+            libc::SECCOMP_RET_ALLOW
+        );
+        assert_eq!(run(&filter, arch, nr("getppid"), [0; 6]), EPERM);
+    }
+
+    #[test]
+    fn rules_with_the_default_action_are_left_out() {
+        let filter = build_filter(&profile(json!({
+            "defaultAction": "SCMP_ACT_ALLOW",
+            "syscalls": [{ "names": ["getpid"], "action": "SCMP_ACT_ALLOW" }]
+        })))
+        .unwrap();
+        let empty = build_filter(&profile(json!({ "defaultAction": "SCMP_ACT_ALLOW" }))).unwrap();
+        assert_eq!(filter.len(), empty.len());
+    }
+
+    #[test]
+    fn argument_comparisons() {
+        let arch = native().audit_arch;
+        let value: u64 = 0x1_0000_0010;
+        let cases = [
+            ("SCMP_CMP_EQ", [false, true, false]),
+            ("SCMP_CMP_NE", [true, false, true]),
+            ("SCMP_CMP_GT", [false, false, true]),
+            ("SCMP_CMP_GE", [false, true, true]),
+            ("SCMP_CMP_LT", [true, false, false]),
+            ("SCMP_CMP_LE", [true, true, false]),
+        ];
+        // below, equal to and above the value, with the high and the low halves deciding
+        let args = [[0x10, value, value + 1], [value - 1, value, 0x2_0000_0000]];
+
+        for (op, expected) in cases {
+            let filter = build_filter(&profile(json!({
+                "defaultAction": "SCMP_ACT_ALLOW",
+                "syscalls": [{
+                    "names": ["getpid"],
+                    "action": "SCMP_ACT_ERRNO",
+                    "args": [{ "index": 1, "value": value, "op": op }]
+                }]
+            })))
+            .unwrap();
+            for args in args {
+                for (arg, expected) in args.iter().zip(expected) {
+                    let ret = run(&filter, arch, nr("getpid"), [0, *arg, 0, 0, 0, 0]);
+                    assert_eq!(ret == EPERM, expected, "{} {:#x}", op, arg);
+                }
+            }
+        }
+
+        let filter = build_filter(&profile(json!({
+            "defaultAction": "SCMP_ACT_ALLOW",
+            "syscalls": [{
+                "names": ["getpid"],
+                "action": "SCMP_ACT_ERRNO",
+                "args": [{
+                    "index": 5,
+                    "value": 0xff00_0000_00ffu64,
+                    "valueTwo": 0x1200_0000_0034u64,
+                    "op": "SCMP_CMP_MASKED_EQ"
+                }]
+            }]
+        })))
+        .unwrap();
+        let masked = |arg| run(&filter, arch, nr("getpid"), [0, 0, 0, 0, 0, arg]);
+        assert_eq!(masked(0x1234_5678_9a34), EPERM);
+        assert_eq!(masked(0x1300_0000_0034), libc::SECCOMP_RET_ALLOW);
+        assert_eq!(masked(0x1200_0000_0035), libc::SECCOMP_RET_ALLOW);
+    }
+
+    #[test]
+    fn invalid_argument_index() {
+        assert!(build_filter(&profile(json!({
+            "defaultAction": "SCMP_ACT_ALLOW",
+            "syscalls": [{
+                "names": ["getpid"],
+                "action": "SCMP_ACT_ERRNO",
+                "args": [{ "index": 6, "value": 0, "op": "SCMP_CMP_EQ" }]
+            }]
+        })))
+        .is_err());
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn rules_apply_to_every_architecture() {
+        let rules = json!([{ "names": ["getpid"], "action": "SCMP_ACT_ERRNO" }]);
+        let getpid_x86 = compat::lookup_x86("getpid").unwrap() as u32;
+        let getpid_x32 = compat::lookup_x32("getpid").unwrap() as u32;
+
+        let filter = build_filter(&profile(json!({
+            "defaultAction": "SCMP_ACT_ALLOW",
+            "syscalls": rules
+        })))
+        .unwrap();
+        assert_eq!(run(&filter, AUDIT_ARCH_X86_64, nr("getpid"), [0; 6]), EPERM);
+        assert_eq!(
+            run(&filter, AUDIT_ARCH_I386, getpid_x86, [0; 6]),
+            RET_FOREIGN_ABI
+        );
+        assert_eq!(
+            run(&filter, AUDIT_ARCH_X86_64, getpid_x32, [0; 6]),
+            RET_FOREIGN_ABI
+        );
+
+        let filter = build_filter(&profile(json!({
+            "defaultAction": "SCMP_ACT_ALLOW",
+            "architectures": ["SCMP_ARCH_X86_64", "SCMP_ARCH_X86", "SCMP_ARCH_X32"],
+            "syscalls": rules
+        })))
+        .unwrap();
+        assert_eq!(run(&filter, AUDIT_ARCH_X86_64, nr("getpid"), [0; 6]), EPERM);
+        assert_eq!(run(&filter, AUDIT_ARCH_I386, getpid_x86, [0; 6]), EPERM);
+        assert_eq!(run(&filter, AUDIT_ARCH_X86_64, getpid_x32, [0; 6]), EPERM);
+        assert_eq!(
+            run(&filter, AUDIT_ARCH_I386, getpid_x86 + 1, [0; 6]),
+            libc::SECCOMP_RET_ALLOW
+        );
+
+        let filter = build_filter(&profile(json!({
+            "defaultAction": "SCMP_ACT_ALLOW",
+            "architectures": ["SCMP_ARCH_X32"],
+            "syscalls": rules
+        })))
+        .unwrap();
+        assert_eq!(run(&filter, AUDIT_ARCH_X86_64, getpid_x32, [0; 6]), EPERM);
+        assert_eq!(
+            run(&filter, AUDIT_ARCH_X86_64, nr("getpid"), [0; 6]),
+            RET_FOREIGN_ABI
+        );
+    }
+
+    #[test]
+    fn unsupported_architecture() {
+        assert!(build_filter(&profile(json!({
+            "defaultAction": "SCMP_ACT_ALLOW",
+            "architectures": ["SCMP_ARCH_S390X"]
+        })))
+        .is_err());
+    }
+}
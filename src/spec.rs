@@ -1,9 +1,10 @@
+use std::collections::HashMap;
 use std::fs::File;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use caps::Capability;
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 #[derive(Default, PartialEq, Serialize, Deserialize, Debug)]
 pub struct Box {
@@ -16,16 +17,81 @@ pub struct Box {
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct User {
-    #[serde(default)]
-    pub uid: u32,
-    #[serde(default)]
-    pub gid: u32,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
     #[serde(default)]
     pub additional_gids: Vec<u32>,
     #[serde(default)]
     pub username: String,
 }
 
+// A capability name such as CAP_CHOWN.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LinuxCapabilityType {
+    pub cap: Capability,
+}
+
+impl Serialize for LinuxCapabilityType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.cap.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for LinuxCapabilityType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        match name.parse() {
+            Ok(cap) => Ok(LinuxCapabilityType { cap }),
+            Err(_) => Err(de::Error::custom(format!("unknown capability {}", name))),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LinuxCapabilities {
+    #[serde(default)]
+    pub bounding: Vec<LinuxCapabilityType>,
+    #[serde(default)]
+    pub effective: Vec<LinuxCapabilityType>,
+    #[serde(default)]
+    pub inheritable: Vec<LinuxCapabilityType>,
+    #[serde(default)]
+    pub permitted: Vec<LinuxCapabilityType>,
+    #[serde(default)]
+    pub ambient: Vec<LinuxCapabilityType>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PosixRlimitType {
+    RlimitAs,
+    RlimitCore,
+    RlimitCpu,
+    RlimitData,
+    RlimitFsize,
+    RlimitLocks,
+    RlimitMemlock,
+    RlimitMsgqueue,
+    RlimitNice,
+    RlimitNofile,
+    RlimitNproc,
+    RlimitRss,
+    RlimitRtprio,
+    RlimitRttime,
+    RlimitSigpending,
+    RlimitStack,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct PosixRlimit {
+    #[serde(rename = "type")]
+    pub typ: PosixRlimitType,
+    #[serde(default)]
+    pub hard: u64,
+    #[serde(default)]
+    pub soft: u64,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Process {
@@ -39,6 +105,9 @@ pub struct Process {
     pub env: Vec<String>,
     #[serde(default)]
     pub cwd: String,
+    pub capabilities: Option<LinuxCapabilities>,
+    #[serde(default)]
+    pub rlimits: Vec<PosixRlimit>,
     #[serde(default)]
     pub no_new_privileges: bool,
     #[serde(default)]
@@ -82,7 +151,7 @@ impl Default for LinuxDeviceType {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LinuxDeviceCgroup {
     #[serde(default)]
     pub allow: bool,
@@ -94,7 +163,7 @@ pub struct LinuxDeviceCgroup {
     pub access: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Default, Serialize, Deserialize, Debug, Clone)]
 pub struct LinuxMemory {
     pub limit: Option<i64>,
     pub reservation: Option<i64>,
@@ -105,7 +174,7 @@ pub struct LinuxMemory {
     pub swappiness: Option<u64>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Default, Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct LinuxCPU {
     pub shares: Option<u64>,
@@ -119,13 +188,13 @@ pub struct LinuxCPU {
     pub mems: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Default, Serialize, Deserialize, Debug, Clone)]
 pub struct LinuxPids {
     #[serde(default)]
     pub limit: i64,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct LinuxWeightDevice {
     #[serde(default)]
@@ -136,7 +205,7 @@ pub struct LinuxWeightDevice {
     pub leaf_weight: Option<u16>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LinuxThrottleDevice {
     #[serde(default)]
     pub major: i64,
@@ -146,7 +215,7 @@ pub struct LinuxThrottleDevice {
     pub rate: u64,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Default, Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct LinuxBlockIO {
     pub blkio_weight: Option<u16>,
@@ -163,7 +232,7 @@ pub struct LinuxBlockIO {
     pub blkio_throttle_write_iops_device: Vec<LinuxThrottleDevice>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct LinuxHugepageLimit {
     #[serde(default)]
@@ -172,7 +241,7 @@ pub struct LinuxHugepageLimit {
     pub limit: i64,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LinuxInterfacePriority {
     #[serde(default)]
     pub name: String,
@@ -180,7 +249,7 @@ pub struct LinuxInterfacePriority {
     pub priority: u32,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct LinuxNetwork {
     #[serde(rename = "classID")]
@@ -189,12 +258,12 @@ pub struct LinuxNetwork {
     pub priorities: Vec<LinuxInterfacePriority>,
 }
 
-#[derive(Default, Serialize, Deserialize, Debug)]
+#[derive(Default, Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct LinuxResources {
     #[serde(default)]
     pub devices: Vec<LinuxDeviceCgroup>,
-    #[serde(default)]
+    #[serde(default, rename = "disableOOMKiller")]
     pub disable_oom_killer: bool,
     pub oom_score_adj: Option<i32>,
     pub memory: Option<LinuxMemory>,
@@ -228,6 +297,15 @@ pub struct LinuxNamespace {
     pub path: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct LinuxIdMapping {
+    #[serde(rename = "containerID")]
+    pub container_id: u32,
+    #[serde(rename = "hostID")]
+    pub host_id: u32,
+    pub size: u32,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct LinuxDevice {
@@ -244,6 +322,101 @@ pub struct LinuxDevice {
     pub gid: Option<u32>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LinuxSeccompAction {
+    ScmpActKill,
+    ScmpActKillProcess,
+    ScmpActKillThread,
+    ScmpActTrap,
+    ScmpActErrno,
+    ScmpActTrace,
+    ScmpActAllow,
+    ScmpActLog,
+    ScmpActNotify,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Arch {
+    ScmpArchNative,
+    ScmpArchX86,
+    ScmpArchX86_64,
+    ScmpArchX32,
+    ScmpArchArm,
+    ScmpArchAarch64,
+    ScmpArchMips,
+    ScmpArchMips64,
+    ScmpArchMips64n32,
+    ScmpArchMipsel,
+    ScmpArchMipsel64,
+    ScmpArchMipsel64n32,
+    ScmpArchPpc,
+    ScmpArchPpc64,
+    ScmpArchPpc64le,
+    ScmpArchS390,
+    ScmpArchS390x,
+    ScmpArchParisc,
+    ScmpArchParisc64,
+    ScmpArchRiscv64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LinuxSeccompFlag {
+    SeccompFilterFlagTsync,
+    SeccompFilterFlagLog,
+    SeccompFilterFlagSpecAllow,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LinuxSeccompOperator {
+    ScmpCmpNe,
+    ScmpCmpLt,
+    ScmpCmpLe,
+    ScmpCmpEq,
+    ScmpCmpGe,
+    ScmpCmpGt,
+    ScmpCmpMaskedEq,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LinuxSeccompArg {
+    pub index: usize,
+    pub value: u64,
+    #[serde(default)]
+    pub value_two: u64,
+    pub op: LinuxSeccompOperator,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LinuxSyscall {
+    pub names: Vec<String>,
+    pub action: LinuxSeccompAction,
+    pub errno_ret: Option<u32>,
+    #[serde(default)]
+    pub args: Vec<LinuxSeccompArg>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LinuxSeccomp {
+    pub default_action: LinuxSeccompAction,
+    pub default_errno_ret: Option<u32>,
+    #[serde(default)]
+    pub architectures: Vec<Arch>,
+    #[serde(default)]
+    pub flags: Vec<LinuxSeccompFlag>,
+    #[serde(default)]
+    pub syscalls: Vec<LinuxSyscall>,
+    pub listener_path: Option<PathBuf>,
+    pub listener_metadata: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Linux {
@@ -251,11 +424,50 @@ pub struct Linux {
     #[serde(default)]
     pub namespaces: Vec<LinuxNamespace>,
     #[serde(default)]
+    pub uid_mappings: Vec<LinuxIdMapping>,
+    #[serde(default)]
+    pub gid_mappings: Vec<LinuxIdMapping>,
+    #[serde(default)]
     pub rootfs_propagation: String,
     #[serde(default)]
     pub devices: Vec<LinuxDevice>,
     #[serde(default)]
     pub mount_label: String,
+    pub cgroups_path: Option<PathBuf>,
+    pub seccomp: Option<LinuxSeccomp>,
+    #[serde(default)]
+    pub masked_paths: Vec<String>,
+    #[serde(default)]
+    pub readonly_paths: Vec<String>,
+    #[serde(default)]
+    pub sysctl: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Hook {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: Vec<String>,
+    pub timeout: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Hooks {
+    #[serde(default)]
+    pub prestart: Vec<Hook>,
+    #[serde(default)]
+    pub create_runtime: Vec<Hook>,
+    #[serde(default)]
+    pub create_container: Vec<Hook>,
+    #[serde(default)]
+    pub start_container: Vec<Hook>,
+    #[serde(default)]
+    pub poststart: Vec<Hook>,
+    #[serde(default)]
+    pub poststop: Vec<Hook>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -265,15 +477,23 @@ pub struct Spec{
     #[serde(default)]
     pub hostname: String,
     #[serde(default)]
+    pub domainname: String,
+    #[serde(default)]
     pub mounts: Vec<Mount>,
+    pub hooks: Option<Hooks>,
     pub linux: Option<Linux>,
+    #[serde(default)]
+    pub annotations: HashMap<String, String>,
 }
 
 impl Spec {
-    pub fn load(path: &str) -> Result<Self> {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
         let file = File::open(path)?;
         let mut spec: Spec = serde_json::from_reader(&file)?;
-        spec.root.path = std::fs::canonicalize(spec.root.path)?;
+        // a relative root path is relative to the bundle holding config.json
+        let bundle = path.parent().unwrap_or_else(|| Path::new(""));
+        spec.root.path = std::fs::canonicalize(bundle.join(&spec.root.path))?;
         Ok(spec)
     }
 }
@@ -5,6 +5,7 @@ use clap::Args;
 use nix::unistd;
 
 use crate::container::{Container, ContainerStatus};
+use crate::hooks;
 use crate::notify_socket::NotifySocket;
 
 #[derive(Debug, Args)]
@@ -34,7 +35,11 @@ impl Start {
         let mut notify_socket = NotifySocket::new(&container.root)?;
         notify_socket.notify_container_start()?;
 
-        container.update_status(ContainerStatus::Running)?.save()?;
+        let container = container.update_status(ContainerStatus::Running)?;
+        container.save()?;
+        if let Some(hooks) = container.spec()?.hooks {
+            hooks::run_hooks_or_warn(&hooks.poststart, &container.state);
+        }
         Ok(())
     }
 }
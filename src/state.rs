@@ -0,0 +1,24 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use clap::Args;
+
+use crate::container::Container;
+
+#[derive(Debug, Args)]
+pub struct State {
+    pub container_id: String,
+}
+
+impl State {
+    pub fn exec(&self, root_path: PathBuf) -> Result<()> {
+        let container_root = root_path.join(&self.container_id);
+        if !container_root.exists() {
+            bail!("{} doesn't exists.", self.container_id)
+        }
+        let container = Container::load(container_root)?.refresh_status()?;
+        log::debug!("State: {:?}", container.state);
+        println!("{}", serde_json::to_string_pretty(&container.state)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use nix::sched::CloneFlags;
+
+// The ipc namespace has its own copy of these keys.
+const IPC_SYSCTLS: &[&str] = &[
+    "kernel.msgmax",
+    "kernel.msgmnb",
+    "kernel.msgmni",
+    "kernel.sem",
+    "kernel.shmall",
+    "kernel.shmmax",
+    "kernel.shmmni",
+    "kernel.shm_rmid_forced",
+];
+
+// Refuse the keys which would change the host instead of the container.
+pub fn validate(sysctl: &HashMap<String, String>, namespaces: CloneFlags) -> Result<()> {
+    for key in sysctl.keys() {
+        let required = if key.starts_with("net.") {
+            CloneFlags::CLONE_NEWNET
+        } else if IPC_SYSCTLS.contains(&key.as_str()) || key.starts_with("fs.mqueue.") {
+            CloneFlags::CLONE_NEWIPC
+        } else if key == "kernel.hostname" || key == "kernel.domainname" {
+            CloneFlags::CLONE_NEWUTS
+        } else {
+            bail!("sysctl {} is not in a separate kernel namespace", key)
+        };
+        if !namespaces.contains(required) {
+            bail!("sysctl {} requires a {:?} namespace", key, required)
+        }
+    }
+    Ok(())
+}
+
+// /proc/sys shows the values of the namespaces of the writing process.
+pub fn write(sysctl: &HashMap<String, String>) -> Result<()> {
+    for (key, value) in sysctl {
+        log::debug!("sysctl {}={}", key, value);
+        let path = PathBuf::from("/proc/sys").join(key.replace('.', "/"));
+        if let Err(e) = fs::write(&path, value) {
+            bail!("failed to set sysctl {}: {}", key, e)
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sysctl(key: &str) -> HashMap<String, String> {
+        HashMap::from([(key.to_string(), "1".to_string())])
+    }
+
+    #[test]
+    fn keys_of_the_container_namespaces() {
+        let namespaces =
+            CloneFlags::CLONE_NEWNET | CloneFlags::CLONE_NEWIPC | CloneFlags::CLONE_NEWUTS;
+        for key in [
+            "net.ipv4.ip_forward",
+            "kernel.shmmax",
+            "fs.mqueue.msg_max",
+            "kernel.hostname",
+        ] {
+            assert!(validate(&sysctl(key), namespaces).is_ok(), "{}", key);
+        }
+        assert!(validate(&HashMap::new(), CloneFlags::empty()).is_ok());
+    }
+
+    #[test]
+    fn keys_without_their_namespace() {
+        assert!(validate(&sysctl("net.ipv4.ip_forward"), CloneFlags::CLONE_NEWIPC).is_err());
+        assert!(validate(&sysctl("kernel.sem"), CloneFlags::CLONE_NEWNET).is_err());
+        assert!(validate(&sysctl("fs.mqueue.queues_max"), CloneFlags::CLONE_NEWUTS).is_err());
+        assert!(validate(&sysctl("kernel.domainname"), CloneFlags::CLONE_NEWIPC).is_err());
+    }
+
+    #[test]
+    fn keys_of_the_host() {
+        let namespaces = CloneFlags::all();
+        assert!(validate(&sysctl("kernel.pid_max"), namespaces).is_err());
+        assert!(validate(&sysctl("vm.swappiness"), namespaces).is_err());
+        assert!(validate(&sysctl("fs.file-max"), namespaces).is_err());
+    }
+}
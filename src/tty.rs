@@ -11,19 +11,14 @@ use nix::unistd::{close, setsid};
 
 use crate::stdio;
 use crate::stdio::FileDescriptor;
+use crate::utils;
 
 pub fn ready(console_fd: FileDescriptor) -> Result<()> {
     let openpty_result = nix::pty::openpty(None, None)?;
-    let data: &[u8] = b"/dev/ptmx";
-    let iov = [nix::sys::uio::IoVec::from_slice(data)];
-    let fds = [openpty_result.master];
-    let cmsg = socket::ControlMessage::ScmRights(&fds);
-    socket::sendmsg(
+    utils::send_fds(
         console_fd.as_raw_fd(),
-        &iov,
-        &[cmsg],
-        socket::MsgFlags::empty(),
-        None,
+        b"/dev/ptmx",
+        &[openpty_result.master],
     )?;
     log::debug!("Send master file descripter to console fd");
 
@@ -44,6 +39,26 @@ pub fn load_console_sockets(
     let csocket = "console-stdout";
     symlink(console_socket, container_dir.join(csocket))?;
 
+    let csocketfd = connect_console_socket(csocket)?;
+    let console = "console";
+    let consolefd = match fcntl::open(
+        &*console,
+        fcntl::OFlag::O_NOCTTY | fcntl::OFlag::O_RDWR,
+        stat::Mode::empty(),
+    ) {
+        Err(e) => {
+            if e != ::nix::Error::Sys(Errno::ENOENT) {
+                bail!("failed to open {}", console);
+            }
+            -1
+        }
+        Ok(fd) => fd,
+    };
+    log::debug!("csocketfd: {:?}, consolefd: {:?}", csocketfd, consolefd);
+    Ok((csocketfd, consolefd.into()))
+}
+
+pub fn connect_console_socket(console_socket: &str) -> Result<FileDescriptor> {
     let mut csocketfd = socket::socket(
         socket::AddressFamily::Unix,
         socket::SockType::Stream,
@@ -52,30 +67,15 @@ pub fn load_console_sockets(
     )?;
     csocketfd = match socket::connect(
         csocketfd,
-        &socket::SockAddr::Unix(socket::UnixAddr::new(&*csocket)?),
+        &socket::SockAddr::Unix(socket::UnixAddr::new(console_socket)?),
     ) {
         Err(e) => {
             if e != ::nix::Error::Sys(Errno::ENOENT) {
-                bail!("failed to open {}", csocket);
+                bail!("failed to open {}", console_socket);
             }
             -1
         }
         Ok(()) => csocketfd,
     };
-    let console = "console";
-    let consolefd = match fcntl::open(
-        &*console,
-        fcntl::OFlag::O_NOCTTY | fcntl::OFlag::O_RDWR,
-        stat::Mode::empty(),
-    ) {
-        Err(e) => {
-            if e != ::nix::Error::Sys(Errno::ENOENT) {
-                bail!("failed to open {}", console);
-            }
-            -1
-        }
-        Ok(fd) => fd,
-    };
-    log::debug!("csocketfd: {:?}, consolefd: {:?}", csocketfd, consolefd);
-    Ok((csocketfd.into(), consolefd.into()))
+    Ok(csocketfd.into())
 }
@@ -0,0 +1,283 @@
+use std::fs::File;
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use clap::Args;
+
+use crate::container::{Container, ContainerStatus};
+use crate::spec::LinuxResources;
+
+#[derive(Debug, Args)]
+pub struct Update {
+    // a file holding the resources in the format of linux.resources of the spec
+    #[clap(short, long)]
+    resources: Option<PathBuf>,
+    // memory limit in bytes, -1 for unlimited
+    #[clap(long, allow_hyphen_values = true)]
+    memory: Option<i64>,
+    // memory plus swap limit in bytes, -1 for unlimited
+    #[clap(long, allow_hyphen_values = true)]
+    memory_swap: Option<i64>,
+    // microseconds of cpu time per period, -1 for unlimited
+    #[clap(long, allow_hyphen_values = true)]
+    cpu_quota: Option<i64>,
+    #[clap(long)]
+    cpu_period: Option<u64>,
+    #[clap(long)]
+    cpu_shares: Option<u64>,
+    #[clap(long)]
+    cpuset_cpus: Option<String>,
+    // 0 or -1 for unlimited
+    #[clap(long, allow_hyphen_values = true)]
+    pids_limit: Option<i64>,
+    #[clap(long)]
+    blkio_weight: Option<u16>,
+    pub container_id: String,
+}
+
+impl Update {
+    pub fn exec(&self, root_path: PathBuf) -> Result<()> {
+        let container_root = root_path.join(&self.container_id);
+        if !container_root.exists() {
+            bail!("{} doesn't exists.", self.container_id)
+        }
+        let container = Container::load(container_root)?.refresh_status()?;
+        if container.status() == ContainerStatus::Stopped {
+            bail!(
+                "{} could not be updated because it was stopped",
+                container.id()
+            )
+        }
+
+        let current = container.resources()?;
+        let mut resources = match &self.resources {
+            Some(path) => {
+                let file: serde_json::Value = serde_json::from_reader(File::open(path)?)?;
+                // disableOOMKiller defaults to false, only the file can tell whether it is given
+                let oom_killer_given = file.get("disableOOMKiller").is_some();
+                merge(current, serde_json::from_value(file)?, oom_killer_given)
+            }
+            None => current,
+        };
+        self.override_resources(&mut resources);
+
+        container.cgroup_manager()?.update(&resources)?;
+        log::debug!("Update resources of {}: {:?}", container.id(), resources);
+        container.save_resources(&resources)?;
+        Ok(())
+    }
+
+    // The flags take precedence over the resources file.
+    fn override_resources(&self, resources: &mut LinuxResources) {
+        if self.memory.is_some() || self.memory_swap.is_some() {
+            let memory = resources.memory.get_or_insert_with(Default::default);
+            if let Some(limit) = self.memory {
+                if self.memory_swap.is_none() {
+                    memory.swap = raise_swap(memory.swap, memory.limit, limit);
+                }
+                memory.limit = Some(limit);
+            }
+            if self.memory_swap.is_some() {
+                memory.swap = self.memory_swap;
+            }
+        }
+        if self.cpu_quota.is_some()
+            || self.cpu_period.is_some()
+            || self.cpu_shares.is_some()
+            || self.cpuset_cpus.is_some()
+        {
+            let cpu = resources.cpu.get_or_insert_with(Default::default);
+            if self.cpu_quota.is_some() {
+                cpu.quota = self.cpu_quota;
+            }
+            if self.cpu_period.is_some() {
+                cpu.period = self.cpu_period;
+            }
+            if self.cpu_shares.is_some() {
+                cpu.shares = self.cpu_shares;
+            }
+            if let Some(cpus) = &self.cpuset_cpus {
+                cpu.cpus = cpus.clone();
+            }
+        }
+        if let Some(limit) = self.pids_limit {
+            resources.pids.get_or_insert_with(Default::default).limit = limit;
+        }
+        if self.blkio_weight.is_some() {
+            resources
+                .block_io
+                .get_or_insert_with(Default::default)
+                .blkio_weight = self.blkio_weight;
+        }
+    }
+}
+
+// The swap limit is the limit of memory and swap together, it can't be lower than the memory
+// limit. A raised memory limit raises the swap limit too, with the same amount of swap.
+fn raise_swap(swap: Option<i64>, current_limit: Option<i64>, limit: i64) -> Option<i64> {
+    match swap {
+        Some(swap) if swap > 0 && limit == -1 => Some(-1),
+        Some(swap) if swap > 0 && swap < limit => {
+            let swap_only = current_limit
+                .filter(|&current| current > 0 && current <= swap)
+                .map_or(0, |current| swap - current);
+            Some(limit + swap_only)
+        }
+        swap => swap,
+    }
+}
+
+// The controllers given in the file replace the current ones, the others are kept.
+fn merge(
+    current: LinuxResources,
+    update: LinuxResources,
+    oom_killer_given: bool,
+) -> LinuxResources {
+    LinuxResources {
+        // the device rules are only applied when the container is created
+        devices: current.devices,
+        disable_oom_killer: if oom_killer_given {
+            update.disable_oom_killer
+        } else {
+            current.disable_oom_killer
+        },
+        oom_score_adj: update.oom_score_adj.or(current.oom_score_adj),
+        memory: update.memory.or(current.memory),
+        cpu: update.cpu.or(current.cpu),
+        pids: update.pids.or(current.pids),
+        block_io: update.block_io.or(current.block_io),
+        hugepage_limits: if update.hugepage_limits.is_empty() {
+            current.hugepage_limits
+        } else {
+            update.hugepage_limits
+        },
+        network: update.network.or(current.network),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const MB: i64 = 1 << 20;
+
+    fn update() -> Update {
+        Update {
+            resources: None,
+            memory: None,
+            memory_swap: None,
+            cpu_quota: None,
+            cpu_period: None,
+            cpu_shares: None,
+            cpuset_cpus: None,
+            pids_limit: None,
+            blkio_weight: None,
+            container_id: "test".to_string(),
+        }
+    }
+
+    fn resources(resources: serde_json::Value) -> LinuxResources {
+        serde_json::from_value(resources).unwrap()
+    }
+
+    fn memory(resources: &LinuxResources) -> (Option<i64>, Option<i64>) {
+        let memory = resources.memory.as_ref().unwrap();
+        (memory.limit, memory.swap)
+    }
+
+    #[test]
+    fn merge_replaces_the_given_controllers() {
+        let current = resources(json!({
+            "devices": [{ "allow": false, "access": "rwm" }],
+            "disableOOMKiller": true,
+            "memory": { "limit": 512 * MB, "swap": 1024 * MB },
+            "cpu": { "shares": 512, "cpus": "0-1" },
+            "pids": { "limit": 100 }
+        }));
+        assert!(current.disable_oom_killer);
+        let merged = merge(
+            current.clone(),
+            resources(json!({ "cpu": { "quota": 50000 }, "devices": [] })),
+            false,
+        );
+
+        assert_eq!(merged.devices.len(), 1);
+        assert!(merged.disable_oom_killer);
+        assert_eq!(memory(&merged), (Some(512 * MB), Some(1024 * MB)));
+        let cpu = merged.cpu.unwrap();
+        assert_eq!(
+            (cpu.quota, cpu.shares, cpu.cpus.as_str()),
+            (Some(50000), None, "")
+        );
+        assert_eq!(merged.pids.unwrap().limit, 100);
+
+        let merged = merge(
+            current,
+            resources(json!({ "disableOOMKiller": false })),
+            true,
+        );
+        assert!(!merged.disable_oom_killer);
+    }
+
+    #[test]
+    fn flags_override_the_resources() {
+        let mut update = update();
+        update.memory_swap = Some(-1);
+        update.cpu_quota = Some(-1);
+        update.cpuset_cpus = Some("2".to_string());
+        update.pids_limit = Some(0);
+        update.blkio_weight = Some(300);
+        let mut resources = resources(json!({
+            "memory": { "limit": 512 * MB, "swap": 1024 * MB },
+            "cpu": { "shares": 512, "quota": 20000 }
+        }));
+        update.override_resources(&mut resources);
+
+        assert_eq!(memory(&resources), (Some(512 * MB), Some(-1)));
+        let cpu = resources.cpu.unwrap();
+        assert_eq!(
+            (cpu.quota, cpu.shares, cpu.cpus.as_str()),
+            (Some(-1), Some(512), "2")
+        );
+        assert_eq!(resources.pids.unwrap().limit, 0);
+        assert_eq!(resources.block_io.unwrap().blkio_weight, Some(300));
+    }
+
+    #[test]
+    fn memory_flag_raises_the_swap() {
+        let current = json!({ "memory": { "limit": 512 * MB, "swap": 1024 * MB } });
+        let cases = [
+            // the same 512M of swap on top of the new limit
+            (2048 * MB, Some(2560 * MB)),
+            // the swap limit is high enough already
+            (768 * MB, Some(1024 * MB)),
+            (256 * MB, Some(1024 * MB)),
+            (-1, Some(-1)),
+        ];
+        for (limit, swap) in cases {
+            let mut update = update();
+            update.memory = Some(limit);
+            let mut resources = resources(current.clone());
+            update.override_resources(&mut resources);
+            assert_eq!(memory(&resources), (Some(limit), swap), "{}", limit);
+        }
+
+        // an unset or unlimited swap stays so
+        for swap in [json!(null), json!(-1), json!(0)] {
+            let mut update = update();
+            update.memory = Some(2048 * MB);
+            let mut resources = resources(json!({ "memory": { "limit": 512 * MB, "swap": swap } }));
+            update.override_resources(&mut resources);
+            assert_eq!(resources.memory.unwrap().swap, swap.as_i64());
+        }
+
+        // both flags are taken as given
+        let mut update = update();
+        update.memory = Some(2048 * MB);
+        update.memory_swap = Some(2048 * MB);
+        let mut resources = resources(current);
+        update.override_resources(&mut resources);
+        assert_eq!(memory(&resources), (Some(2048 * MB), Some(2048 * MB)));
+    }
+}
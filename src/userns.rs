@@ -0,0 +1,66 @@
+use std::fs;
+use std::process::Command;
+
+use anyhow::{bail, Result};
+use nix::unistd::{self, Pid};
+
+use crate::spec::{Linux, LinuxIdMapping};
+
+// Map the ids of the user namespace of pid, which waits for it before going on.
+pub fn write_id_mappings(pid: Pid, linux: &Linux) -> Result<()> {
+    if linux.uid_mappings.is_empty() || linux.gid_mappings.is_empty() {
+        bail!("a user namespace needs uidMappings and gidMappings")
+    }
+
+    let privileged = unistd::geteuid().is_root();
+    if privileged || maps_only(&linux.uid_mappings, unistd::geteuid().as_raw()) {
+        write_map(pid, "uid_map", &linux.uid_mappings)?;
+    } else {
+        run_map_helper("newuidmap", pid, &linux.uid_mappings)?;
+    }
+
+    if privileged {
+        write_map(pid, "gid_map", &linux.gid_mappings)?;
+    } else if maps_only(&linux.gid_mappings, unistd::getegid().as_raw()) {
+        // an unprivileged process may only map its own gid once setgroups is denied
+        fs::write(format!("/proc/{}/setgroups", pid), "deny")?;
+        write_map(pid, "gid_map", &linux.gid_mappings)?;
+    } else {
+        run_map_helper("newgidmap", pid, &linux.gid_mappings)?;
+    }
+    log::debug!("Write id mappings of {:?}", pid);
+    Ok(())
+}
+
+// A process can always map its own id alone.
+fn maps_only(mappings: &[LinuxIdMapping], id: u32) -> bool {
+    mappings.len() == 1 && mappings[0].host_id == id && mappings[0].size == 1
+}
+
+fn write_map(pid: Pid, file: &str, mappings: &[LinuxIdMapping]) -> Result<()> {
+    let data: String = mappings
+        .iter()
+        .map(|m| format!("{} {} {}\n", m.container_id, m.host_id, m.size))
+        .collect();
+    if let Err(e) = fs::write(format!("/proc/{}/{}", pid, file), data) {
+        bail!("failed to write {}: {}", file, e)
+    }
+    Ok(())
+}
+
+// newuidmap and newgidmap allow the ranges delegated in /etc/subuid and /etc/subgid.
+fn run_map_helper(helper: &str, pid: Pid, mappings: &[LinuxIdMapping]) -> Result<()> {
+    let mut command = Command::new(helper);
+    command.arg(pid.to_string());
+    for m in mappings {
+        command
+            .arg(m.container_id.to_string())
+            .arg(m.host_id.to_string())
+            .arg(m.size.to_string());
+    }
+    match command.status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => bail!("{} failed: {}", helper, status),
+        Err(e) => bail!("failed to run {}: {}", helper, e),
+    }
+}
@@ -1,26 +1,239 @@
-use std::env;
 use std::ffi::CString;
+use std::fs;
+use std::os::unix::ffi::OsStringExt;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::io::RawFd;
+use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Ok, Result};
 use nix::errno::Errno;
+use nix::sys::signal::{self, SigSet, Signal};
+use nix::sys::socket::{self, ControlMessage, ControlMessageOwned, MsgFlags};
+use nix::sys::uio::IoVec;
+use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
 use nix::unistd;
+use nix::unistd::{Gid, Pid, Uid};
 
-pub fn do_exec(path: &str, args: &[String]) -> Result<()> {
-    let p = CString::new(path.to_string()).unwrap();
+use crate::spec::{PosixRlimit, PosixRlimitType, User};
+
+// The PATH used when the container process has none.
+const DEFAULT_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+// Signals received by the runtime that are passed on to the container process.
+const FORWARDED_SIGNALS: [Signal; 8] = [
+    Signal::SIGHUP,
+    Signal::SIGINT,
+    Signal::SIGQUIT,
+    Signal::SIGTERM,
+    Signal::SIGUSR1,
+    Signal::SIGUSR2,
+    Signal::SIGWINCH,
+    Signal::SIGCONT,
+];
+
+// Execute the container process with an environment made only of `env`.
+pub fn do_exec(path: &str, args: &[String], env: &[String]) -> Result<()> {
+    let p = CString::new(find_executable(path, env)?.into_os_string().into_vec())?;
     let a: Vec<CString> = args
         .iter()
         .map(|s| CString::new(s.to_string()).unwrap_or_default())
         .collect();
+    let e: Vec<CString> = env
+        .iter()
+        .map(|s| CString::new(s.to_string()).unwrap_or_default())
+        .collect();
+
+    log::debug!("filename: {:?}, args: {:?}, env: {:?}", p, a, e);
+    unistd::execve(&p, &a, &e)?;
+    log::debug!("finish execve");
+    Ok(())
+}
+
+// Look up a program in the PATH of the container process rather than the one of the runtime.
+fn find_executable(name: &str, env: &[String]) -> Result<PathBuf> {
+    if name.contains('/') {
+        return Ok(PathBuf::from(name));
+    }
+    let path = env
+        .iter()
+        .find_map(|e| e.strip_prefix("PATH="))
+        .unwrap_or(DEFAULT_PATH);
+    for dir in path.split(':') {
+        let candidate = Path::new(dir).join(name);
+        if let Result::Ok(metadata) = fs::metadata(&candidate) {
+            if metadata.is_file() && metadata.permissions().mode() & 0o111 != 0 {
+                return Ok(candidate);
+            }
+        }
+    }
+    bail!("executable file not found in $PATH: {}", name)
+}
 
-    log::debug!("filename: {:?}, args: {:?}", p, a);
-    unistd::execvp(&p, &a)?;
-    log::debug!("finish execvp");
+pub fn setdomainname(name: &str) -> Result<()> {
+    if unsafe { libc::setdomainname(name.as_ptr() as *const libc::c_char, name.len()) } != 0 {
+        bail!("failed to set domainname {}: {}", name, Errno::last());
+    }
+    Ok(())
+}
+
+// Start the container process in `cwd`, creating it if missing.
+pub fn set_cwd(cwd: &str) -> Result<()> {
+    let cwd = if cwd.is_empty() { "/" } else { cwd };
+    fs::create_dir_all(cwd)?;
+    unistd::chdir(cwd)?;
+    Ok(())
+}
+
+pub fn setid(uid: Uid, gid: Gid) -> Result<()> {
+    if let Err(e) = prctl::set_keep_capabilities(true) {
+        bail!("set keep capabilities returned {}", e);
+    };
+    unistd::setresgid(gid, gid, gid)?;
+    unistd::setresuid(uid, uid, uid)?;
+
+    if let Err(e) = prctl::set_keep_capabilities(false) {
+        bail!("set keep capabilities returned {}", e);
+    };
+    Ok(())
+}
+
+pub fn set_rlimit(rlimit: &PosixRlimit) -> Result<()> {
+    let resource = match rlimit.typ {
+        PosixRlimitType::RlimitAs => libc::RLIMIT_AS,
+        PosixRlimitType::RlimitCore => libc::RLIMIT_CORE,
+        PosixRlimitType::RlimitCpu => libc::RLIMIT_CPU,
+        PosixRlimitType::RlimitData => libc::RLIMIT_DATA,
+        PosixRlimitType::RlimitFsize => libc::RLIMIT_FSIZE,
+        PosixRlimitType::RlimitLocks => libc::RLIMIT_LOCKS,
+        PosixRlimitType::RlimitMemlock => libc::RLIMIT_MEMLOCK,
+        PosixRlimitType::RlimitMsgqueue => libc::RLIMIT_MSGQUEUE,
+        PosixRlimitType::RlimitNice => libc::RLIMIT_NICE,
+        PosixRlimitType::RlimitNofile => libc::RLIMIT_NOFILE,
+        PosixRlimitType::RlimitNproc => libc::RLIMIT_NPROC,
+        PosixRlimitType::RlimitRss => libc::RLIMIT_RSS,
+        PosixRlimitType::RlimitRtprio => libc::RLIMIT_RTPRIO,
+        PosixRlimitType::RlimitRttime => libc::RLIMIT_RTTIME,
+        PosixRlimitType::RlimitSigpending => libc::RLIMIT_SIGPENDING,
+        PosixRlimitType::RlimitStack => libc::RLIMIT_STACK,
+    };
+    let limit = libc::rlimit {
+        rlim_cur: rlimit.soft,
+        rlim_max: rlimit.hard,
+    };
+    if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+        bail!("failed to set {:?}: {}", rlimit.typ, Errno::last());
+    }
     Ok(())
 }
 
-pub fn set_env_val(env: &Vec<String>) {
-    for i in 0..env.len() {
-        let split_path: Vec<&str> = env[i].split('=').collect();
-        env::set_var(split_path[0], split_path[1]);
+pub fn send_fds(socket: RawFd, data: &[u8], fds: &[RawFd]) -> Result<()> {
+    let iov = [IoVec::from_slice(data)];
+    let cmsg = ControlMessage::ScmRights(fds);
+    socket::sendmsg(socket, &iov, &[cmsg], MsgFlags::empty(), None)?;
+    Ok(())
+}
+
+pub fn recv_fd(socket: RawFd) -> Result<RawFd> {
+    let mut buf = [0; 64];
+    let iov = [IoVec::from_mut_slice(&mut buf)];
+    let mut cmsg_buffer = nix::cmsg_space!([RawFd; 1]);
+    let msg = socket::recvmsg(socket, &iov, Some(&mut cmsg_buffer), MsgFlags::empty())?;
+    for cmsg in msg.cmsgs() {
+        if let ControlMessageOwned::ScmRights(fds) = cmsg {
+            if let Some(&fd) = fds.first() {
+                return Ok(fd);
+            }
+        }
+    }
+    bail!("no file descriptor received")
+}
+
+// Switch to the user of the container process. The capabilities are kept so that they can be
+// set from the spec afterwards.
+pub fn set_user(user: &User) -> Result<()> {
+    let (uid, gid) = match user.uid {
+        Some(uid) => (uid, user.gid.unwrap_or(0)),
+        None if !user.username.is_empty() => {
+            let (uid, gid) = lookup_user(&user.username)?;
+            (uid, user.gid.unwrap_or(gid))
+        }
+        None => (0, user.gid.unwrap_or(0)),
+    };
+    log::debug!("set user: uid={}, gid={}", uid, gid);
+
+    let gids: Vec<Gid> = user
+        .additional_gids
+        .iter()
+        .map(|&gid| Gid::from_raw(gid))
+        .collect();
+    // a user namespace mapped without privileges denies setgroups
+    let denied = fs::read_to_string("/proc/self/setgroups")
+        .map(|setgroups| setgroups.trim() == "deny")
+        .unwrap_or(false);
+    if !denied {
+        unistd::setgroups(&gids)?;
+    } else if !gids.is_empty() {
+        bail!("additionalGids can't be set as setgroups is denied in the user namespace")
+    }
+    setid(Uid::from_raw(uid), Gid::from_raw(gid))
+}
+
+// Find the uid and gid of a user in /etc/passwd of the container.
+fn lookup_user(username: &str) -> Result<(u32, u32)> {
+    let passwd = fs::read_to_string("/etc/passwd")?;
+    for line in passwd.lines() {
+        let fields: Vec<&str> = line.split(':').collect();
+        if fields.len() < 4 || fields[0] != username {
+            continue;
+        }
+        return Ok((fields[2].parse()?, fields[3].parse()?));
+    }
+    bail!("unable to find user {} in /etc/passwd", username)
+}
+
+fn forwarded_sigset() -> SigSet {
+    let mut sigset = SigSet::empty();
+    sigset.add(Signal::SIGCHLD);
+    for &sig in FORWARDED_SIGNALS.iter() {
+        sigset.add(sig);
+    }
+    sigset
+}
+
+// Keep the forwarded signals pending until wait_for_child takes them, instead of letting them
+// kill the runtime halfway. The processes forked meanwhile must unblock them.
+pub fn block_forwarded_signals() -> Result<()> {
+    forwarded_sigset().thread_block()?;
+    Ok(())
+}
+
+pub fn unblock_forwarded_signals() -> Result<()> {
+    forwarded_sigset().thread_unblock()?;
+    Ok(())
+}
+
+// Wait for the only child of the runtime to exit while forwarding signals to `pid`. The child is
+// the intermediate process that waits for the container process and exits with its exit code.
+pub fn wait_for_child(pid: Pid) -> Result<i32> {
+    let sigset = forwarded_sigset();
+    sigset.thread_block()?;
+
+    loop {
+        match waitpid(None, Some(WaitPidFlag::WNOHANG))? {
+            WaitStatus::Exited(_, status) => return Ok(status),
+            WaitStatus::Signaled(_, sig, _) => return Ok(128 + sig as i32),
+            _ => {}
+        }
+        match sigset.wait()? {
+            Signal::SIGCHLD => continue,
+            sig => {
+                log::debug!("forward {:?} to {:?}", sig, pid);
+                match signal::kill(pid, sig) {
+                    // the process exited, its parent is about to
+                    Err(nix::Error::Sys(Errno::ESRCH)) => {}
+                    result => result?,
+                }
+            }
+        }
     }
 }